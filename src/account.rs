@@ -0,0 +1,336 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use hmac::Hmac;
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::game::PID;
+use crate::i18n::{Localize, DEFAULT_LANGUAGE};
+
+mod test;
+
+const ACCOUNTS_FILE: &str = "accounts.json";
+const PBKDF2_ROUNDS: u32 = 10_000;
+const SALT_LENGTH: usize = 16;
+const HASH_LENGTH: usize = 32;
+const TOKEN_LENGTH: usize = 32;
+pub const SESSION_LIFETIME: u64 = 30 * 24 * 60 * 60;
+const MIN_NICKNAME_LENGTH: usize = 3;
+const MAX_NICKNAME_LENGTH: usize = 20;
+const MIN_PASSWORD_LENGTH: usize = 6;
+
+#[derive(Serialize, Deserialize)]
+struct Account {
+    pid: PID,
+    nickname: String,
+    salt: String,
+    password_hash: String,
+    /// The player who registered the bot, `None` for human players. Bots have
+    /// no password and connect with API tokens.
+    #[serde(default)]
+    owner: Option<PID>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Session {
+    pid: PID,
+    /// Unix time in seconds.
+    expires: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Accounts {
+    accounts: HashMap<String, Account>,
+    sessions: HashMap<String, Session>,
+    /// API tokens of bots.
+    #[serde(default)]
+    tokens: HashMap<String, PID>,
+    counter: PID,
+    #[serde(skip)]
+    nicknames: HashMap<PID, String>,
+    /// `None` keeps the accounts in memory only.
+    #[serde(skip)]
+    file: Option<PathBuf>,
+}
+
+/// A salted password hash. It takes long to derive, so it is made without the
+/// accounts locked.
+pub struct Password {
+    salt: String,
+    hash: String,
+}
+
+#[derive(Debug, Serialize)]
+pub enum AccountError {
+    InvalidNickname,
+    InvalidPassword,
+    NicknameTaken,
+    WrongCredentials,
+    NotYourBot,
+}
+
+impl std::fmt::Display for AccountError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.localize(DEFAULT_LANGUAGE))
+    }
+}
+
+impl std::error::Error for AccountError {}
+
+fn random_hex(len: usize) -> String {
+    let bytes = (0..len)
+        .map(|_| thread_rng().gen::<u8>())
+        .collect::<Vec<_>>();
+    hex::encode(bytes)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs())
+}
+
+fn is_valid_nickname(nickname: &str) -> bool {
+    nickname.chars().count() >= MIN_NICKNAME_LENGTH
+        && nickname.chars().count() <= MAX_NICKNAME_LENGTH
+        && nickname
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+impl Password {
+    /// A password of a new account.
+    pub fn new(password: &str) -> Result<Password, AccountError> {
+        if password.chars().count() < MIN_PASSWORD_LENGTH {
+            return Err(AccountError::InvalidPassword);
+        }
+        Ok(Password::with_salt(password, random_hex(SALT_LENGTH)))
+    }
+
+    /// A password to check against the account with the salt.
+    pub fn with_salt(password: &str, salt: String) -> Password {
+        let mut hash = [0u8; HASH_LENGTH];
+        pbkdf2::pbkdf2::<Hmac<Sha256>>(
+            password.as_bytes(),
+            salt.as_bytes(),
+            PBKDF2_ROUNDS,
+            &mut hash,
+        );
+        let hash = hex::encode(hash);
+        Password { salt, hash }
+    }
+}
+
+impl Accounts {
+    pub fn load() -> Accounts {
+        let mut accounts = match File::open(ACCOUNTS_FILE) {
+            Ok(file) => match serde_json::from_reader::<_, Accounts>(file) {
+                Ok(accounts) => accounts,
+                Err(e) => {
+                    warn!("ACCOUNTS can't parse {}: {}", ACCOUNTS_FILE, e);
+                    Accounts::default()
+                }
+            },
+            Err(_) => Accounts::default(),
+        };
+        accounts.nicknames = accounts
+            .accounts
+            .values()
+            .map(|account| (account.pid, account.nickname.clone()))
+            .collect();
+        accounts.file = Some(PathBuf::from(ACCOUNTS_FILE));
+        info!("ACCOUNTS {} loaded", accounts.accounts.len());
+        accounts
+    }
+
+    fn save(&self) {
+        let path = match &self.file {
+            Some(path) => path,
+            None => return,
+        };
+        match File::create(path) {
+            Ok(file) => {
+                if let Err(e) = serde_json::to_writer(file, self) {
+                    warn!("ACCOUNTS can't save {}: {}", ACCOUNTS_FILE, e);
+                }
+            }
+            Err(e) => warn!("ACCOUNTS can't create {}: {}", ACCOUNTS_FILE, e),
+        }
+    }
+
+    fn new_session(&mut self, pid: PID) -> String {
+        let token = random_hex(TOKEN_LENGTH);
+        let now = now();
+        self.sessions.retain(|_, session| session.expires > now);
+        let expires = now + SESSION_LIFETIME;
+        self.sessions
+            .insert(token.clone(), Session { pid, expires });
+        self.save();
+        token
+    }
+
+    fn add(
+        &mut self,
+        nickname: &str,
+        salt: String,
+        password_hash: String,
+        owner: Option<PID>,
+    ) -> PID {
+        self.counter += 1;
+        let pid = self.counter;
+        self.accounts.insert(
+            nickname.to_lowercase(),
+            Account {
+                pid,
+                nickname: nickname.to_string(),
+                salt,
+                password_hash,
+                owner,
+            },
+        );
+        self.nicknames.insert(pid, nickname.to_string());
+        pid
+    }
+
+    pub fn register(&mut self, nickname: &str, password: Password) -> Result<String, AccountError> {
+        let nickname = nickname.trim();
+        if !is_valid_nickname(nickname) {
+            return Err(AccountError::InvalidNickname);
+        }
+        if self.accounts.contains_key(&nickname.to_lowercase()) {
+            return Err(AccountError::NicknameTaken);
+        }
+
+        let pid = self.add(nickname, password.salt, password.hash, None);
+        info!("ACCOUNT {} registered as {}", pid, nickname);
+
+        Ok(self.new_session(pid))
+    }
+
+    /// Registers a bot of the player, returns its API token.
+    pub fn register_bot(&mut self, owner: PID, nickname: &str) -> Result<String, AccountError> {
+        let nickname = nickname.trim();
+        if !is_valid_nickname(nickname) {
+            return Err(AccountError::InvalidNickname);
+        }
+        if self.accounts.contains_key(&nickname.to_lowercase()) {
+            return Err(AccountError::NicknameTaken);
+        }
+
+        let pid = self.add(nickname, String::new(), String::new(), Some(owner));
+        info!("ACCOUNT {} registered bot {} as {}", owner, pid, nickname);
+
+        Ok(self.new_token(pid))
+    }
+
+    fn new_token(&mut self, pid: PID) -> String {
+        let token = random_hex(TOKEN_LENGTH);
+        self.tokens.retain(|_, &mut bot| bot != pid);
+        self.tokens.insert(token.clone(), pid);
+        self.save();
+        token
+    }
+
+    /// Replaces the API token of the player's bot, the old one stops working.
+    pub fn reset_token(&mut self, owner: PID, nickname: &str) -> Result<String, AccountError> {
+        let pid = match self.accounts.get(&nickname.trim().to_lowercase()) {
+            Some(account) if account.owner == Some(owner) => account.pid,
+            _ => return Err(AccountError::NotYourBot),
+        };
+        info!("ACCOUNT {} reset the token of bot {}", owner, pid);
+
+        Ok(self.new_token(pid))
+    }
+
+    /// The salt to hash the password of a login with. Unknown nicknames get a
+    /// random one, so that they take as long to check as the known ones.
+    pub fn salt(&self, nickname: &str) -> String {
+        match self.accounts.get(&nickname.trim().to_lowercase()) {
+            Some(account) => account.salt.clone(),
+            None => random_hex(SALT_LENGTH),
+        }
+    }
+
+    pub fn login(&mut self, nickname: &str, password: &Password) -> Result<String, AccountError> {
+        let pid = match self.accounts.get(&nickname.trim().to_lowercase()) {
+            Some(account)
+                if account.owner.is_none()
+                    && account.salt == password.salt
+                    && account.password_hash == password.hash =>
+            {
+                account.pid
+            }
+            _ => return Err(AccountError::WrongCredentials),
+        };
+        info!("ACCOUNT {} logged in", pid);
+
+        Ok(self.new_session(pid))
+    }
+
+    pub fn logout(&mut self, token: &str) {
+        if self.sessions.remove(token).is_some() {
+            self.save();
+        }
+    }
+
+    pub fn session_pid(&self, token: &str) -> Option<PID> {
+        self.sessions
+            .get(token)
+            .filter(|session| session.expires > now())
+            .map(|session| session.pid)
+    }
+
+    pub fn token_pid(&self, token: &str) -> Option<PID> {
+        self.tokens.get(token).copied()
+    }
+
+    pub fn is_bot(&self, pid: PID) -> bool {
+        self.nicknames
+            .get(&pid)
+            .and_then(|nickname| self.accounts.get(&nickname.to_lowercase()))
+            .is_some_and(|account| account.owner.is_some())
+    }
+
+    /// The player behind the account, the owner of a bot.
+    fn person(&self, pid: PID) -> PID {
+        self.nicknames
+            .get(&pid)
+            .and_then(|nickname| self.accounts.get(&nickname.to_lowercase()))
+            .and_then(|account| account.owner)
+            .unwrap_or(pid)
+    }
+
+    /// Whether both accounts are the same player or the player's bots.
+    pub fn same_person(&self, a: PID, b: PID) -> bool {
+        self.person(a) == self.person(b)
+    }
+
+    /// Nicknames of the player's bots.
+    pub fn bots(&self, owner: PID) -> Vec<String> {
+        let mut bots = self
+            .accounts
+            .values()
+            .filter(|account| account.owner == Some(owner))
+            .map(|account| account.nickname.clone())
+            .collect::<Vec<_>>();
+        bots.sort();
+        bots
+    }
+
+    pub fn pid(&self, nickname: &str) -> Option<PID> {
+        self.accounts
+            .get(&nickname.trim().to_lowercase())
+            .map(|account| account.pid)
+    }
+
+    pub fn nickname(&self, pid: PID) -> String {
+        match self.nicknames.get(&pid) {
+            Some(nickname) => nickname.clone(),
+            None => format!("#{}", pid),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::account::*;
+
+    #[test]
+    fn account_test_same_person() {
+        let mut accounts = Accounts::default();
+        let alice = accounts.add("alice", String::new(), String::new(), None);
+        let bob = accounts.add("bob", String::new(), String::new(), None);
+        let alice_bot = accounts.add("alice_bot", String::new(), String::new(), Some(alice));
+        let alice_bot2 = accounts.add("alice_bot2", String::new(), String::new(), Some(alice));
+        let bob_bot = accounts.add("bob_bot", String::new(), String::new(), Some(bob));
+
+        assert!(accounts.same_person(alice, alice));
+        assert!(accounts.same_person(alice, alice_bot));
+        assert!(accounts.same_person(alice_bot, alice));
+        assert!(accounts.same_person(alice_bot, alice_bot2));
+        assert!(!accounts.same_person(alice, bob));
+        assert!(!accounts.same_person(alice, bob_bot));
+        assert!(!accounts.same_person(alice_bot, bob_bot));
+        assert!(accounts.is_bot(alice_bot));
+        assert!(!accounts.is_bot(alice));
+    }
+
+    fn login(
+        accounts: &mut Accounts,
+        nickname: &str,
+        password: &str,
+    ) -> Result<String, AccountError> {
+        let password = Password::with_salt(password, accounts.salt(nickname));
+        accounts.login(nickname, &password)
+    }
+
+    #[test]
+    fn account_test_register() {
+        let mut accounts = Accounts::default();
+        let sid = accounts
+            .register("Alice", Password::new("secret").unwrap())
+            .unwrap();
+        let alice = accounts.pid("alice").unwrap();
+        assert_eq!(accounts.session_pid(&sid), Some(alice));
+        assert_eq!(accounts.nickname(alice), "Alice");
+
+        let password = Password::new("secret").unwrap();
+        assert!(matches!(
+            accounts.register("ALICE", password),
+            Err(AccountError::NicknameTaken)
+        ));
+        let password = Password::new("secret").unwrap();
+        assert!(matches!(
+            accounts.register("a", password),
+            Err(AccountError::InvalidNickname)
+        ));
+        assert!(matches!(
+            Password::new("short"),
+            Err(AccountError::InvalidPassword)
+        ));
+    }
+
+    #[test]
+    fn account_test_login() {
+        let mut accounts = Accounts::default();
+        let first = accounts
+            .register("alice", Password::new("secret").unwrap())
+            .unwrap();
+        let alice = accounts.pid("alice").unwrap();
+
+        let sid = login(&mut accounts, " Alice ", "secret").unwrap();
+        assert_ne!(sid, first);
+        assert_eq!(accounts.session_pid(&sid), Some(alice));
+        assert_eq!(accounts.session_pid(&first), Some(alice));
+    }
+
+    #[test]
+    fn account_test_wrong_password() {
+        let mut accounts = Accounts::default();
+        accounts
+            .register("alice", Password::new("secret").unwrap())
+            .unwrap();
+        accounts
+            .register_bot(accounts.pid("alice").unwrap(), "alice_bot")
+            .unwrap();
+
+        let wrong = |result| matches!(result, Err(AccountError::WrongCredentials));
+        assert!(wrong(login(&mut accounts, "alice", "Secret")));
+        assert!(wrong(login(&mut accounts, "bob", "secret")));
+        // Bots have no password
+        assert!(wrong(login(&mut accounts, "alice_bot", "")));
+    }
+
+    #[test]
+    fn account_test_session_expiry() {
+        let mut accounts = Accounts::default();
+        let alice = accounts.add("alice", String::new(), String::new(), None);
+        let expires = now() + SESSION_LIFETIME;
+        accounts.sessions.insert(
+            "new".to_string(),
+            Session {
+                pid: alice,
+                expires,
+            },
+        );
+        let expires = now() - 1;
+        accounts.sessions.insert(
+            "old".to_string(),
+            Session {
+                pid: alice,
+                expires,
+            },
+        );
+
+        assert_eq!(accounts.session_pid("new"), Some(alice));
+        assert_eq!(accounts.session_pid("old"), None);
+        assert_eq!(accounts.session_pid("unknown"), None);
+    }
+}
@@ -0,0 +1,79 @@
+//! Bots connected to `/ws?arena` play each other, rated on a leaderboard of
+//! their own.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{GameResult, PID};
+use crate::journal;
+use crate::rating::Ratings;
+
+mod test;
+
+const ARENA_FILE: &str = "arena.jsonl";
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ArenaMatch {
+    pub replay: usize,
+    pub players: Vec<PID>,
+    pub winner: Option<PID>,
+    pub finished: u64,
+}
+
+#[derive(Default)]
+pub struct Arena {
+    matches: Vec<ArenaMatch>,
+    pub ratings: Ratings,
+}
+
+impl Arena {
+    pub fn load() -> Arena {
+        let mut arena = Arena::default();
+        for m in journal::load::<ArenaMatch>(Path::new(ARENA_FILE)) {
+            arena.rate(&m);
+            arena.matches.push(m);
+        }
+        info!("ARENA {} matches loaded", arena.matches.len());
+        arena
+    }
+
+    fn rate(&mut self, m: &ArenaMatch) {
+        self.ratings.update(&GameResult {
+            players: m.players.clone(),
+            winner: m.winner,
+            ..GameResult::default()
+        });
+    }
+
+    fn add(&mut self, replay: usize, result: &GameResult) -> &ArenaMatch {
+        let m = ArenaMatch {
+            replay,
+            players: result.players.clone(),
+            winner: result.winner,
+            finished: result.finished,
+        };
+        self.rate(&m);
+        self.matches.push(m);
+        self.matches.last().unwrap()
+    }
+
+    /// Rates a finished arena game stored as the replay and logs it.
+    pub fn record(&mut self, replay: usize, result: &GameResult) {
+        let m = self.add(replay, result);
+        if let Err(e) = journal::append(Path::new(ARENA_FILE), m) {
+            warn!("ARENA can't save {}: {}", ARENA_FILE, e);
+        }
+    }
+
+    /// Returns at most `count` last matches, of the bot if it is given,
+    /// newest first.
+    pub fn matches(&self, bot: Option<PID>, count: usize) -> Vec<&ArenaMatch> {
+        self.matches
+            .iter()
+            .rev()
+            .filter(|m| bot.is_none_or(|bot| m.players.contains(&bot)))
+            .take(count)
+            .collect()
+    }
+}
@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::game::GameResult;
+
+    use crate::arena::*;
+
+    fn result(players: Vec<PID>, winner: Option<PID>, finished: u64) -> GameResult {
+        GameResult {
+            players,
+            winner,
+            finished,
+            ..GameResult::default()
+        }
+    }
+
+    #[test]
+    fn arena_test_matches() {
+        let mut arena = Arena::default();
+        arena.add(3, &result(vec![1, 2], Some(1), 10));
+        arena.add(5, &result(vec![2, 3], None, 20));
+        arena.add(6, &result(vec![3, 1], Some(3), 30));
+
+        assert_eq!(arena.ratings.get(1).games, 2);
+        assert_eq!(arena.ratings.get(1).wins, 1);
+        assert_eq!(arena.ratings.get(2).games, 1);
+
+        let replays =
+            |matches: Vec<&ArenaMatch>| matches.iter().map(|m| m.replay).collect::<Vec<_>>();
+        assert_eq!(replays(arena.matches(None, 10)), vec![6, 5, 3]);
+        assert_eq!(replays(arena.matches(None, 2)), vec![6, 5]);
+        assert_eq!(replays(arena.matches(Some(1), 10)), vec![6, 3]);
+        assert!(arena.matches(Some(4), 10).is_empty());
+    }
+
+    #[test]
+    fn arena_test_ratings_from_log() {
+        let mut arena = Arena::default();
+        arena.add(1, &result(vec![1, 2], Some(1), 10));
+        arena.add(2, &result(vec![2, 3], Some(2), 20));
+        arena.add(3, &result(vec![3, 1], None, 30));
+        arena.add(4, &result(vec![1, 3], Some(3), 40));
+
+        let mut rebuilt = Arena::default();
+        for m in &arena.matches {
+            rebuilt.rate(m);
+        }
+        for pid in 1..=3 {
+            let (rating, rebuilt) = (arena.ratings.get(pid), rebuilt.ratings.get(pid));
+            assert_eq!(rating.rating, rebuilt.rating);
+            assert_eq!((rating.games, rating.wins), (rebuilt.games, rebuilt.wins));
+        }
+    }
+}
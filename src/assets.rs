@@ -0,0 +1,247 @@
+//! The `static` and `templates` directories embedded into the binary. Files in
+//! the override directory are used instead, reread on every request and not
+//! cached by browsers.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chrono::{DateTime, Utc};
+use include_dir::{include_dir, Dir, File};
+use sha2::{Digest, Sha256};
+
+use crate::web::{Request, Response};
+
+mod test;
+
+static STATIC_DIR: Dir = include_dir!("$CARGO_MANIFEST_DIR/static");
+static TEMPLATES_DIR: Dir = include_dir!("$CARGO_MANIFEST_DIR/templates");
+
+/// How long browsers may use embedded files without revalidation, seconds.
+const CACHE_MAX_AGE: u64 = 3600;
+
+const HTTP_DATE_FORMAT: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+pub fn data_by_url(url: &str) -> &'static str {
+    // The favicon is a PNG image despite its name
+    if url == "/favicon.ico" {
+        return "image/png";
+    }
+
+    let extension = match url.rfind('.') {
+        Some(pos) if !url[pos..].contains('/') => url[pos + 1..].to_ascii_lowercase(),
+        _ => String::new(),
+    };
+
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=UTF-8",
+        "css" => "text/css; charset=UTF-8",
+        "js" | "mjs" => "text/javascript; charset=UTF-8",
+        "json" | "map" => "application/json",
+        "webmanifest" => "application/manifest+json",
+        "txt" => "text/plain; charset=UTF-8",
+        "csv" => "text/csv; charset=UTF-8",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "bmp" => "image/bmp",
+        "ico" => "image/x-icon",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "eot" => "application/vnd.ms-fontobject",
+        "mp3" => "audio/mpeg",
+        "ogg" | "oga" => "audio/ogg",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "wasm" => "application/wasm",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
+
+#[derive(Clone)]
+pub struct Asset {
+    pub content_type: &'static str,
+    pub data: Cow<'static, [u8]>,
+    pub etag: String,
+    pub modified: Option<SystemTime>,
+    /// Whether browsers may cache the file without revalidation.
+    pub cacheable: bool,
+}
+
+impl Asset {
+    fn new(url: &str, data: Cow<'static, [u8]>, modified: Option<SystemTime>) -> Asset {
+        Asset {
+            content_type: data_by_url(url),
+            etag: format!("\"{}\"", hex::encode(&Sha256::digest(&data)[..16])),
+            data,
+            modified,
+            cacheable: true,
+        }
+    }
+
+    /// Whether the browser's copy of the file is still valid. `If-None-Match`
+    /// takes precedence over `If-Modified-Since`.
+    pub fn not_modified(
+        &self,
+        if_none_match: Option<&str>,
+        if_modified_since: Option<&str>,
+    ) -> bool {
+        if let Some(etags) = if_none_match {
+            return etags
+                .split(',')
+                .any(|etag| etag.trim() == self.etag || etag.trim() == "*");
+        }
+
+        match (if_modified_since, self.modified) {
+            (Some(since), Some(modified)) => match DateTime::parse_from_rfc2822(since) {
+                Ok(since) => unix_seconds(modified) <= since.timestamp(),
+                Err(_) => false,
+            },
+            _ => false,
+        }
+    }
+
+    pub fn into_response(self, request: &Request) -> Response {
+        let not_modified = self.not_modified(
+            request.header("If-None-Match"),
+            request.header("If-Modified-Since"),
+        );
+        let mut response = if not_modified {
+            Response::empty(304)
+        } else {
+            Response::from_data(self.content_type, self.data.into_owned())
+        };
+
+        response = response.with_unique_header("ETag", self.etag);
+        if let Some(modified) = self.modified {
+            response = response.with_unique_header("Last-Modified", http_date(modified));
+        }
+        if self.cacheable {
+            response.with_public_cache(CACHE_MAX_AGE)
+        } else {
+            response.with_unique_header("Cache-Control", "no-cache")
+        }
+    }
+}
+
+pub struct Assets {
+    embedded: HashMap<String, Asset>,
+    override_dir: Option<PathBuf>,
+}
+
+impl Assets {
+    pub fn load(override_dir: Option<PathBuf>) -> Assets {
+        let mut files = Vec::new();
+        walk(&STATIC_DIR, &mut files);
+
+        let embedded: HashMap<_, _> = files
+            .into_iter()
+            .map(|file| {
+                let url = url_of(file.path());
+                let modified = file.metadata().map(|metadata| metadata.modified());
+                let asset = Asset::new(&url, Cow::Borrowed(file.contents()), modified);
+                (url, asset)
+            })
+            .collect();
+
+        info!("ASSETS {} embedded", embedded.len());
+        if let Some(dir) = &override_dir {
+            info!("ASSETS overridden from {}", dir.display());
+        }
+
+        Assets {
+            embedded,
+            override_dir,
+        }
+    }
+
+    /// Finds the static file for the url, e.g. `/game.js`.
+    pub fn get(&self, url: &str) -> Option<Asset> {
+        let path = relative_path(url)?;
+
+        if let Some(dir) = &self.override_dir {
+            let path = dir.join("static").join(&path);
+            if let Ok(data) = fs::read(&path) {
+                let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+                let mut asset = Asset::new(url, Cow::Owned(data), modified);
+                asset.cacheable = false;
+                return Some(asset);
+            }
+        }
+
+        self.embedded.get(url).cloned()
+    }
+
+    /// Names and sources of all templates.
+    pub fn templates(&self) -> Vec<(String, String)> {
+        let mut files = Vec::new();
+        walk(&TEMPLATES_DIR, &mut files);
+
+        files
+            .into_iter()
+            .filter_map(|file| {
+                let name = file.path().to_string_lossy().replace('\\', "/");
+                let source = self
+                    .override_dir
+                    .as_ref()
+                    .and_then(|dir| {
+                        fs::read_to_string(dir.join("templates").join(file.path())).ok()
+                    })
+                    .or_else(|| file.contents_utf8().map(|s| s.to_string()));
+                match source {
+                    Some(source) => Some((name, source)),
+                    None => {
+                        warn!("ASSETS template {} isn't UTF-8", name);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+fn walk(dir: &'static Dir<'static>, files: &mut Vec<&'static File<'static>>) {
+    files.extend(dir.files());
+    for dir in dir.dirs() {
+        walk(dir, files);
+    }
+}
+
+fn url_of(path: &Path) -> String {
+    format!("/{}", path.to_string_lossy().replace('\\', "/"))
+}
+
+/// The path of the url inside the static directory, `None` if the url
+/// points outside of it.
+fn relative_path(url: &str) -> Option<PathBuf> {
+    let path = Path::new(url.strip_prefix('/')?);
+    if path.as_os_str().is_empty() || !path.components().all(|c| matches!(c, Component::Normal(_)))
+    {
+        return None;
+    }
+    Some(path.to_path_buf())
+}
+
+fn unix_seconds(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs() as i64,
+        Err(_) => 0,
+    }
+}
+
+pub fn http_date(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time)
+        .format(HTTP_DATE_FORMAT)
+        .to_string()
+}
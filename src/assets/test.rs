@@ -0,0 +1,82 @@
+#[cfg(test)]
+mod tests {
+
+    use std::fs;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::assets::*;
+
+    #[test]
+    fn assets_test_data_by_url() {
+        assert_eq!(data_by_url("/game.js"), "text/javascript; charset=UTF-8");
+        assert_eq!(data_by_url("404.html"), "text/html; charset=UTF-8");
+        assert_eq!(data_by_url("/cards_font.ttf"), "font/ttf");
+        assert_eq!(data_by_url("/favicon.ico"), "image/png");
+        assert_eq!(data_by_url("/icons/card.ICO"), "image/x-icon");
+        assert_eq!(data_by_url("/robots.txt"), "text/plain; charset=UTF-8");
+        assert_eq!(data_by_url("/fonts.v2/font"), "application/octet-stream");
+        assert_eq!(data_by_url("/"), "application/octet-stream");
+    }
+
+    #[test]
+    fn assets_test_embedded() {
+        let assets = Assets::load(None);
+
+        let game = assets.get("/game.js").unwrap();
+        assert_eq!(game.content_type, "text/javascript; charset=UTF-8");
+        assert!(game.cacheable);
+        assert!(!game.data.is_empty());
+        assert_eq!(assets.get("/game.js").unwrap().etag, game.etag);
+        assert_ne!(assets.get("/robots.txt").unwrap().etag, game.etag);
+
+        assert!(assets.get("/missing.js").is_none());
+        assert!(assets.get("/../Cargo.toml").is_none());
+        assert!(assets.get("/").is_none());
+        assert!(assets.get("game.js").is_none());
+
+        let templates = assets.templates();
+        assert!(templates.iter().any(|(name, _)| name == "404.html"));
+        assert!(templates.iter().any(|(name, _)| name == "sitemap.txt"));
+    }
+
+    #[test]
+    fn assets_test_override() {
+        let dir = std::env::temp_dir().join(format!("poker-durak-assets-{}", std::process::id()));
+        fs::create_dir_all(dir.join("static")).unwrap();
+        fs::create_dir_all(dir.join("templates")).unwrap();
+        fs::write(dir.join("static").join("robots.txt"), "User-agent: *\n").unwrap();
+        fs::write(dir.join("templates").join("404.html"), "Nothing here").unwrap();
+
+        let assets = Assets::load(Some(dir.clone()));
+        let robots = assets.get("/robots.txt").unwrap();
+        assert_eq!(&robots.data[..], b"User-agent: *\n");
+        assert!(!robots.cacheable);
+        assert!(assets.get("/game.js").unwrap().cacheable);
+        assert!(assets
+            .templates()
+            .contains(&("404.html".to_string(), "Nothing here".to_string())));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn assets_test_not_modified() {
+        let mut asset = Assets::load(None).get("/game.js").unwrap();
+        asset.modified = Some(UNIX_EPOCH + Duration::from_secs(1_600_000_000));
+        let etag = asset.etag.clone();
+
+        assert_eq!(
+            http_date(asset.modified.unwrap()),
+            "Sun, 13 Sep 2020 12:26:40 GMT"
+        );
+
+        assert!(!asset.not_modified(None, None));
+        assert!(asset.not_modified(Some(&etag), None));
+        assert!(asset.not_modified(Some(&format!("\"other\", {}", etag)), None));
+        assert!(!asset.not_modified(Some("\"other\""), None));
+        assert!(asset.not_modified(None, Some("Sun, 13 Sep 2020 12:26:40 GMT")));
+        assert!(!asset.not_modified(None, Some("Sun, 13 Sep 2020 12:26:39 GMT")));
+        assert!(!asset.not_modified(None, Some("yesterday")));
+        assert!(!asset.not_modified(Some("\"other\""), Some("Sun, 13 Sep 2020 12:26:40 GMT")));
+    }
+}
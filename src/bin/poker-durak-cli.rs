@@ -0,0 +1,443 @@
+//! A terminal client for the poker durak server, with `--offline` the game
+//! runs in-process.
+
+use std::collections::HashMap;
+use std::env::{args, var};
+use std::io::{stdin, stdout, BufRead, ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rand::thread_rng;
+use tokio::runtime::Runtime;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::stream::MaybeTlsStream;
+use tokio_tungstenite::tungstenite::{self, Message, WebSocket};
+
+use poker_durak::bot::choose_step;
+use poker_durak::client::*;
+use poker_durak::game::{game_worker, GameChannelClient, Rules, Step, TimeControl, PID};
+use poker_durak::i18n::{Language, Localize};
+use poker_durak::protocol::*;
+
+const DEFAULT_SERVER: &str = "localhost:8000";
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Pings are sent more often than any sane `heartbit_interval` of the server.
+const PING_INTERVAL: Duration = Duration::from_secs(5);
+
+struct Options {
+    server: String,
+    room: Option<String>,
+    language: Language,
+    offline: bool,
+    /// Bots of an offline game.
+    bots: Option<usize>,
+    /// The nickname to log in with, or the human players of an offline game.
+    nicknames: Vec<String>,
+}
+
+fn usage() -> String {
+    format!(
+        "Usage: poker-durak-cli [--server HOST:PORT] [--room NAME] [--lang ru|en] NICKNAME\n\
+         \x20      poker-durak-cli --offline [--bots N] [--lang ru|en] [NICKNAME...]\n\n\
+         The server is {} by default, the password is taken from POKER_DURAK_PASSWORD\n\
+         or asked for.\n\n\
+         An offline game is played by the named players passing the terminal and by\n\
+         the bots, one bot plays against a single player by default.\n",
+        DEFAULT_SERVER
+    )
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        server: DEFAULT_SERVER.to_string(),
+        room: None,
+        language: var("LANG")
+            .ok()
+            .and_then(|lang| Language::from_code(&lang.replace('_', "-")))
+            .unwrap_or(Language::En),
+        offline: false,
+        bots: None,
+        nicknames: Vec::new(),
+    };
+
+    let mut args = args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("no value for {}", name));
+        match arg.as_str() {
+            "--server" => options.server = value("--server")?,
+            "--room" => options.room = Some(value("--room")?),
+            "--lang" => {
+                let code = value("--lang")?;
+                options.language =
+                    Language::from_code(&code).ok_or(format!("unknown language {}", code))?;
+            }
+            "--offline" => options.offline = true,
+            "--bots" => {
+                let bots = value("--bots")?;
+                options.bots = Some(
+                    bots.parse()
+                        .map_err(|_| format!("invalid --bots {}", bots))?,
+                );
+            }
+            "-h" | "--help" => return Err(String::new()),
+            _ if arg.starts_with('-') => return Err(format!("unknown option {}", arg)),
+            _ => options.nicknames.push(arg),
+        }
+    }
+
+    match options.nicknames.len() {
+        _ if options.offline => Ok(options),
+        0 => Err("no nickname".to_string()),
+        1 => Ok(options),
+        _ => Err(format!("unexpected argument {}", options.nicknames[1])),
+    }
+}
+
+fn url_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Logs in and returns the session id, or the text of the error.
+fn login(options: &Options, password: &str) -> Result<String, String> {
+    let body = format!(
+        "nickname={}&password={}",
+        url_encode(&options.nicknames[0]),
+        url_encode(password)
+    );
+    let mut stream = TcpStream::connect(&options.server).map_err(|e| e.to_string())?;
+    write!(
+        stream,
+        "POST /login HTTP/1.1\r\nHost: {}\r\nContent-Type: application/x-www-form-urlencoded\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        options.server,
+        body.len(),
+        body
+    )
+    .map_err(|e| e.to_string())?;
+
+    let mut response = String::new();
+    stream
+        .read_to_string(&mut response)
+        .map_err(|e| e.to_string())?;
+
+    let mut sid = None;
+    for line in response.lines().take_while(|line| !line.is_empty()) {
+        let (name, value) = match line.find(':') {
+            Some(pos) => (line[..pos].to_ascii_lowercase(), line[pos + 1..].trim()),
+            None => continue,
+        };
+        if name == "set-cookie" && value.starts_with("sid=") {
+            sid = value[4..].split(';').next().map(|sid| sid.to_string());
+        } else if name == "location" {
+            if let Some(pos) = value.find("error=") {
+                let key = format!("account_error.{}", &value[pos + 6..]);
+                return Err(options.language.text(&key).unwrap_or(value).to_string());
+            }
+        }
+    }
+    sid.ok_or_else(|| "no session in the server's answer".to_string())
+}
+
+type Socket = WebSocket<MaybeTlsStream<TcpStream>>;
+
+fn send(socket: &mut Socket, message: &ClientMessage) -> Result<(), String> {
+    socket
+        .send(Message::Text(serde_json::to_string(message).unwrap()))
+        .map_err(|e| e.to_string())
+}
+
+fn draw(table: &Table) {
+    print!("\x1b[2J\x1b[H{}\n> ", table.render());
+    stdout().flush().ok();
+}
+
+fn say(text: &str) {
+    print!("{}\n> ", text);
+    stdout().flush().ok();
+}
+
+fn play(options: &Options, sid: &str) -> Result<(), String> {
+    let query = match &options.room {
+        Some(room) => format!("?room={}", url_encode(room)),
+        None => String::new(),
+    };
+    let mut request = format!("ws://{}/ws{}", options.server, query)
+        .into_client_request()
+        .map_err(|e| e.to_string())?;
+    let headers = request.headers_mut();
+    headers.insert("Cookie", format!("sid={}", sid).parse().unwrap());
+    headers.insert("Sec-WebSocket-Protocol", "echo".parse().unwrap());
+    let (mut socket, _) = tungstenite::connect(request).map_err(|e| e.to_string())?;
+    if let MaybeTlsStream::Plain(stream) = socket.get_mut() {
+        stream
+            .set_read_timeout(Some(POLL_INTERVAL))
+            .map_err(|e| e.to_string())?;
+    }
+
+    let (lines_tx, lines) = mpsc::channel();
+    thread::spawn(move || {
+        for line in stdin().lock().lines().map_while(Result::ok) {
+            if lines_tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let text = |key: &str| options.language.text(key).unwrap_or(key).to_string();
+    let mut table = Table::new(options.language);
+    let mut last_ping = Instant::now();
+    send(
+        &mut socket,
+        &ClientMessage::Hello {
+            version: PROTOCOL_VERSION,
+        },
+    )?;
+
+    loop {
+        match socket.read() {
+            Ok(Message::Text(message)) => match serde_json::from_str(&message) {
+                Ok(ServerMessage::Welcome { version, nickname }) => {
+                    table.update(ServerMessage::Welcome { version, nickname });
+                    say(&text("cli.waiting"));
+                }
+                Ok(ServerMessage::ProtocolError { .. }) => {
+                    return Err(format!("the server rejected protocol {}", PROTOCOL_VERSION))
+                }
+                Ok(ServerMessage::YouArePlaying) => return Err(text("cli.playing_elsewhere")),
+                Ok(message) => {
+                    if table.update(message) {
+                        draw(&table);
+                    }
+                }
+                // Messages of newer protocol versions are skipped
+                Err(_) => (),
+            },
+            Ok(Message::Close(_)) => break,
+            Ok(_) => (),
+            Err(tungstenite::Error::Io(e))
+                if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => {}
+            Err(e) => return Err(e.to_string()),
+        }
+
+        match table.result {
+            Some(true) => {
+                println!("\n{}", text("cli.won"));
+                break;
+            }
+            Some(false) => {
+                println!("\n{}", text("cli.lost"));
+                break;
+            }
+            None => (),
+        }
+
+        while let Ok(line) = lines.try_recv() {
+            match table.parse_command(&line) {
+                Ok(Command::Step(step)) => send(&mut socket, &ClientMessage::MakeStep { step })?,
+                Ok(Command::Say(text)) => send(&mut socket, &ClientMessage::SendMessage { text })?,
+                Ok(Command::Exit) => send(&mut socket, &ClientMessage::Exit)?,
+                Ok(Command::Redraw) => draw(&table),
+                Ok(Command::Help) => say(table.help()),
+                Err(e) => say(&table.error_text(&e)),
+            }
+        }
+
+        if last_ping.elapsed() > PING_INTERVAL {
+            send(&mut socket, &ClientMessage::Ping)?;
+            last_ping = Instant::now();
+        }
+    }
+
+    socket.close(None).ok();
+    Ok(())
+}
+
+/// Plays an offline game, the players with the lowest PIDs are the humans.
+fn play_offline(options: &Options) -> Result<(), String> {
+    let text = |key: &str| options.language.text(key).unwrap_or(key).to_string();
+    let mut nicknames = options.nicknames.clone();
+    if nicknames.is_empty() {
+        nicknames.push(text("cli.you"));
+    }
+    let humans = nicknames.len();
+    let bots = options.bots.unwrap_or(if humans == 1 { 1 } else { 0 });
+    nicknames.extend((1..=bots).map(|i| format!("{} {}", text("cli.bot"), i)));
+
+    // Nobody is in a hurry offline
+    let rules = Rules {
+        time_control: TimeControl {
+            turn: 0,
+            ..TimeControl::default()
+        },
+        ..Rules::default()
+    };
+    if nicknames.len() < 2 || nicknames.len() >= rules.max_players() {
+        return Err(format!(
+            "a game needs from 2 to {} players",
+            rules.max_players() - 1
+        ));
+    }
+
+    // The game worker is a task, the terminal waits for its answers
+    let runtime = Runtime::new().map_err(|e| e.to_string())?;
+    let (requests, requests_rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut events = HashMap::new();
+    let mut clients = Vec::new();
+    for pid in 0..nicknames.len() {
+        let (event_tx, event) = tokio::sync::mpsc::unbounded_channel();
+        events.insert(pid, event_tx);
+        clients.push(GameChannelClient(requests.clone(), pid, event));
+    }
+    runtime.spawn(game_worker(events, requests_rx, 0, rules, None));
+
+    let nickname = |pid: PID| nicknames[pid].clone();
+    let mut tables = (0..humans)
+        .map(|pid| {
+            let mut table = Table::new(options.language);
+            table.nickname = nickname(pid);
+            table
+        })
+        .collect::<Vec<_>>();
+    let mut lines = stdin().lock().lines().map_while(Result::ok);
+    let mut shown = None;
+
+    loop {
+        let view = runtime
+            .block_on(clients[0].get_view())
+            .map_err(|e| e.to_string())?;
+        if let Some(winner) = view.winner {
+            match humans {
+                1 if winner == 0 => println!("\n{}", text("cli.won")),
+                1 => println!("\n{}", text("cli.lost")),
+                _ => println!("\n{} {}", nickname(winner), text("cli.won_game")),
+            }
+            break;
+        }
+
+        let pid = view.stepping_player;
+        if pid >= humans {
+            let view = runtime
+                .block_on(clients[pid].get_view())
+                .map_err(|e| e.to_string())?;
+            let step = choose_step(&view.cards, &view.state, &mut thread_rng());
+            for table in tables.iter_mut() {
+                table.step_notice(&nickname(pid), &step);
+            }
+            runtime
+                .block_on(clients[pid].make_step(step))
+                .map_err(|e| e.to_string())?
+                .result
+                .map_err(|e| e.to_string())?;
+            continue;
+        }
+
+        // The hand of the previous player is hidden until the next one is ready
+        if humans > 1 && shown != Some(pid) {
+            print!(
+                "\x1b[2J\x1b[H{} {}, {}",
+                text("cli.pass_to"),
+                nickname(pid),
+                text("cli.press_enter")
+            );
+            stdout().flush().ok();
+            if lines.next().is_none() {
+                return Ok(());
+            }
+        }
+        shown = Some(pid);
+
+        let view = runtime
+            .block_on(clients[pid].get_view())
+            .map_err(|e| e.to_string())?;
+        tables[pid].update(ServerMessage::game_snapshot(&view, nickname));
+        draw(&tables[pid]);
+        loop {
+            let line = match lines.next() {
+                Some(line) => line,
+                None => return Ok(()),
+            };
+            match tables[pid].parse_command(&line) {
+                Ok(Command::Step(step)) => {
+                    let step = Step::from(step);
+                    match runtime
+                        .block_on(clients[pid].make_step(step.clone()))
+                        .map_err(|e| e.to_string())?
+                        .result
+                    {
+                        Ok(()) => {
+                            for (other, table) in tables.iter_mut().enumerate() {
+                                if other != pid {
+                                    table.step_notice(&nickname(pid), &step);
+                                }
+                            }
+                            break;
+                        }
+                        Err(e) => say(&e.localize(options.language)),
+                    }
+                }
+                Ok(Command::Say(message)) => {
+                    for table in tables.iter_mut() {
+                        table.notice(format!("{}: {}", nickname(pid), message));
+                    }
+                    draw(&tables[pid]);
+                }
+                Ok(Command::Exit) => return Ok(()),
+                Ok(Command::Redraw) => draw(&tables[pid]),
+                Ok(Command::Help) => say(tables[pid].help()),
+                Err(e) => say(&tables[pid].error_text(&e)),
+            }
+        }
+    }
+
+    for client in clients {
+        runtime.block_on(client.exit()).ok();
+    }
+    Ok(())
+}
+
+fn main() {
+    let options = match parse_options() {
+        Ok(options) => options,
+        Err(e) => {
+            if !e.is_empty() {
+                eprintln!("poker-durak-cli: {}\n", e);
+            }
+            eprint!("{}", usage());
+            std::process::exit(2);
+        }
+    };
+
+    if options.offline {
+        if let Err(e) = play_offline(&options) {
+            eprintln!("poker-durak-cli: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let password = match var("POKER_DURAK_PASSWORD") {
+        Ok(password) => password,
+        Err(_) => {
+            print!("{}", options.language.text("cli.password").unwrap_or(""));
+            stdout().flush().ok();
+            let mut password = String::new();
+            stdin().lock().read_line(&mut password).ok();
+            password.trim_end_matches(&['\r', '\n'][..]).to_string()
+        }
+    };
+
+    let result = login(&options, &password).and_then(|sid| play(&options, &sid));
+    if let Err(e) = result {
+        eprintln!("poker-durak-cli: {}", e);
+        std::process::exit(1);
+    }
+}
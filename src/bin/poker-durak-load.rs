@@ -0,0 +1,326 @@
+//! A load test of the poker durak server. It creates accounts and rated games,
+//! so it is meant for a server of its own.
+
+use std::env::{args, var};
+use std::time::{Duration, Instant};
+
+use futures_util::{SinkExt, StreamExt};
+use rand::thread_rng;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{interval, timeout};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+use poker_durak::bot::choose_step;
+use poker_durak::comb::Comb;
+use poker_durak::game::{Board, State};
+use poker_durak::protocol::*;
+
+const DEFAULT_SERVER: &str = "localhost:8000";
+const DEFAULT_GAMES: usize = 100;
+const DEFAULT_PREFIX: &str = "load";
+const DEFAULT_PASSWORD: &str = "load-test";
+/// Pings are sent more often than any sane `heartbit_interval` of the server.
+const PING_INTERVAL: Duration = Duration::from_secs(5);
+/// A player giving no sign of life for this long is counted as failed.
+const SILENCE_LIMIT: Duration = Duration::from_secs(120);
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+struct Options {
+    server: String,
+    games: usize,
+    prefix: String,
+    room: Option<String>,
+}
+
+/// What a player has seen of the server.
+#[derive(Default)]
+struct PlayerStats {
+    /// From the connection to the `Opponents` message.
+    waited: Option<Duration>,
+    /// From every `MakeStep` to its answer.
+    steps: Vec<Duration>,
+    step_errors: usize,
+    /// Whether the game ended with `GameWinner` or `GameLoser`.
+    finished: bool,
+}
+
+fn usage() -> String {
+    format!(
+        "Usage: poker-durak-load [--server HOST:PORT] [--games N] [--prefix NAME] [--room NAME]\n\n\
+         Plays N games at once ({} by default) on the server ({} by default) with\n\
+         players named NAME1, NAME2, ... ({} by default). The password of the players\n\
+         is taken from POKER_DURAK_PASSWORD, \"{}\" by default.\n",
+        DEFAULT_GAMES, DEFAULT_SERVER, DEFAULT_PREFIX, DEFAULT_PASSWORD
+    )
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        server: DEFAULT_SERVER.to_string(),
+        games: DEFAULT_GAMES,
+        prefix: DEFAULT_PREFIX.to_string(),
+        room: None,
+    };
+    let mut args = args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--server" => options.server = value()?,
+            "--games" => {
+                options.games = value()?
+                    .parse()
+                    .map_err(|_| "--games needs a number".to_string())?
+            }
+            "--prefix" => options.prefix = value()?,
+            "--room" => options.room = Some(value()?),
+            "--help" => return Err(String::new()),
+            _ => return Err(format!("unknown argument {}", arg)),
+        }
+    }
+    if options.games == 0 {
+        return Err("--games needs a positive number".to_string());
+    }
+    Ok(options)
+}
+
+/// Posts the form and returns the session id from the answer, `None` if the
+/// server redirects with an error.
+async fn post_form(server: &str, path: &str, body: &str) -> Result<Option<String>, String> {
+    let mut stream = TcpStream::connect(server)
+        .await
+        .map_err(|e| e.to_string())?;
+    let request = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/x-www-form-urlencoded\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        path,
+        server,
+        body.len(),
+        body
+    );
+    stream
+        .write_all(request.as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut response = String::new();
+    stream
+        .read_to_string(&mut response)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(response
+        .lines()
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(name, _)| name.eq_ignore_ascii_case("set-cookie"))
+        .filter_map(|(_, value)| value.trim().strip_prefix("sid="))
+        .filter_map(|value| value.split(';').next())
+        .find(|sid| !sid.is_empty())
+        .map(|sid| sid.to_string()))
+}
+
+/// Registers the player or logs in if the nickname is taken.
+async fn session(server: &str, nickname: &str, password: &str) -> Result<String, String> {
+    let body = format!("nickname={}&password={}", nickname, password);
+    if let Some(sid) = post_form(server, "/register", &body).await? {
+        return Ok(sid);
+    }
+    post_form(server, "/login", &body)
+        .await?
+        .ok_or_else(|| format!("{} can't log in", nickname))
+}
+
+async fn send(socket: &mut Socket, message: &ClientMessage) -> Result<(), String> {
+    socket
+        .send(Message::Text(serde_json::to_string(message).unwrap()))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Plays one game as the bot would.
+async fn play(options: &Options, sid: &str, stats: &mut PlayerStats) -> Result<(), String> {
+    let query = match &options.room {
+        Some(room) => format!("?room={}", room),
+        None => String::new(),
+    };
+    let mut request = format!("ws://{}/ws{}", options.server, query)
+        .into_client_request()
+        .map_err(|e| e.to_string())?;
+    let headers = request.headers_mut();
+    headers.insert("Cookie", format!("sid={}", sid).parse().unwrap());
+    headers.insert("Sec-WebSocket-Protocol", "echo".parse().unwrap());
+
+    let connected = Instant::now();
+    let (mut socket, _) = connect_async(request).await.map_err(|e| e.to_string())?;
+    send(
+        &mut socket,
+        &ClientMessage::Hello {
+            version: PROTOCOL_VERSION,
+        },
+    )
+    .await?;
+
+    let mut ping = interval(PING_INTERVAL);
+    let mut step_sent = None;
+    loop {
+        let message = tokio::select! {
+            message = timeout(SILENCE_LIMIT, socket.next()) => message,
+            _ = ping.tick() => {
+                send(&mut socket, &ClientMessage::Ping).await?;
+                continue;
+            }
+        };
+        let text = match message {
+            Ok(Some(Ok(Message::Text(text)))) => text,
+            Ok(Some(Ok(Message::Close(_)))) | Ok(None) => {
+                return Err("the server closed the connection".to_string())
+            }
+            Ok(Some(Ok(_))) => continue,
+            Ok(Some(Err(e))) => return Err(e.to_string()),
+            Err(_) => return Err("the server is silent".to_string()),
+        };
+
+        match serde_json::from_str(&text) {
+            Ok(ServerMessage::Opponents { .. }) => stats.waited = Some(connected.elapsed()),
+            Ok(ServerMessage::YourTurn { board, cards, .. }) => {
+                let state = match board {
+                    Some(board) => State::Active(Board {
+                        comb: Comb::new(board.comb).ok_or("no combination on the board")?,
+                        cards: board.cards,
+                    }),
+                    None => State::Passive,
+                };
+                let step = choose_step(&cards, &state, &mut thread_rng());
+                send(&mut socket, &ClientMessage::MakeStep { step: step.into() }).await?;
+                step_sent = Some(Instant::now());
+            }
+            Ok(ServerMessage::YouMadeStep { .. }) => {
+                if let Some(sent) = step_sent.take() {
+                    stats.steps.push(sent.elapsed());
+                }
+            }
+            Ok(ServerMessage::StepError { .. }) => {
+                stats.step_errors += 1;
+                step_sent = None;
+            }
+            Ok(ServerMessage::GameWinner) | Ok(ServerMessage::GameLoser) => {
+                stats.finished = true;
+                break;
+            }
+            Ok(ServerMessage::YouArePlaying) => return Err("already playing".to_string()),
+            Ok(ServerMessage::ProtocolError { .. }) => {
+                return Err(format!("the server rejected protocol {}", PROTOCOL_VERSION))
+            }
+            _ => (),
+        }
+    }
+
+    socket.close(None).await.ok();
+    Ok(())
+}
+
+/// The given share of the sorted durations.
+fn percentile(sorted: &[Duration], share: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::default();
+    }
+    sorted[((sorted.len() - 1) as f64 * share).round() as usize]
+}
+
+#[tokio::main]
+async fn main() {
+    let options = match parse_options() {
+        Ok(options) => options,
+        Err(e) => {
+            if !e.is_empty() {
+                eprintln!("poker-durak-load: {}\n", e);
+            }
+            eprint!("{}", usage());
+            std::process::exit(2);
+        }
+    };
+    let password = var("POKER_DURAK_PASSWORD").unwrap_or_else(|_| DEFAULT_PASSWORD.to_string());
+    let players = options.games * 2;
+
+    println!("Logging in {} players", players);
+    let logins = (1..=players)
+        .map(|i| {
+            let server = options.server.clone();
+            let nickname = format!("{}{}", options.prefix, i);
+            let password = password.clone();
+            tokio::spawn(async move { session(&server, &nickname, &password).await })
+        })
+        .collect::<Vec<_>>();
+    let mut sids = Vec::new();
+    for login in logins {
+        match login.await.unwrap() {
+            Ok(sid) => sids.push(sid),
+            Err(e) => {
+                eprintln!("poker-durak-load: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    println!("Playing {} games", options.games);
+    let started = Instant::now();
+    let options = std::sync::Arc::new(options);
+    let tasks = sids
+        .into_iter()
+        .map(|sid| {
+            let options = std::sync::Arc::clone(&options);
+            tokio::spawn(async move {
+                let mut stats = PlayerStats::default();
+                let result = play(&options, &sid, &mut stats).await;
+                (stats, result)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut waited = Vec::new();
+    let mut steps = Vec::new();
+    let mut step_errors = 0;
+    let mut finished = 0;
+    let mut failed = 0;
+    for task in tasks {
+        let (stats, result) = task.await.unwrap();
+        if let Err(e) = result {
+            eprintln!("poker-durak-load: {}", e);
+            failed += 1;
+        }
+        waited.extend(stats.waited);
+        steps.extend(stats.steps);
+        step_errors += stats.step_errors;
+        finished += stats.finished as usize;
+    }
+    waited.sort();
+    steps.sort();
+
+    println!(
+        "{} of {} players finished their games in {:.1} s, {} failed",
+        finished,
+        players,
+        started.elapsed().as_secs_f64(),
+        failed
+    );
+    println!(
+        "Waiting for an opponent: median {:?}, max {:?}",
+        percentile(&waited, 0.5),
+        percentile(&waited, 1.0)
+    );
+    println!(
+        "{} steps answered: median {:?}, 95% {:?}, 99% {:?}, max {:?}, {} errors",
+        steps.len(),
+        percentile(&steps, 0.5),
+        percentile(&steps, 0.95),
+        percentile(&steps, 0.99),
+        percentile(&steps, 1.0),
+        step_errors
+    );
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,51 @@
+//! A bot for offline games, it picks a step from what its player sees: the
+//! cards in hand and the board.
+//!
+//! The bot gets rid of as many cards as it can. It plays the longest
+//! combination it has, the weakest of the longest ones, beats the board with
+//! the weakest combination that is strong enough and takes the combination
+//! only if it can't beat it. Now and then it plays a random combination
+//! instead, otherwise bots which can't beat each other's combinations pass
+//! them around forever.
+
+use std::collections::HashSet;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::card::Card;
+use crate::comb::Comb;
+use crate::game::{State, Step};
+
+mod test;
+
+/// One play in this many is a random one.
+const RANDOM_PLAY: u32 = 4;
+
+/// The step of the bot with the cards, it is always a valid one if the bot
+/// is the stepping player.
+pub fn choose_step(cards: &HashSet<Card>, state: &State, rng: &mut impl Rng) -> Step {
+    match state {
+        State::Passive => {
+            let hand = cards.iter().copied().collect::<Vec<_>>();
+            let combs = Comb::all(&hand);
+            let comb = if rng.gen_ratio(1, RANDOM_PLAY) {
+                combs.choose(rng)
+            } else {
+                combs
+                    .iter()
+                    .max_by(|a, b| a.cards.len().cmp(&b.cards.len()).then(b.cmp(a)))
+            };
+            comb.map_or(Step::GetCard, |comb| Step::GiveComb(comb.cards.clone()))
+        }
+        State::Active(board) => {
+            let own = |comb: &Comb| comb.cards.intersection(cards).count();
+            let table = cards.union(&board.cards).copied().collect::<Vec<_>>();
+            Comb::all(&table)
+                .into_iter()
+                .filter(|comb| *comb > board.comb && own(comb) > 0)
+                .min_by(|a, b| a.cmp(b).then(own(b).cmp(&own(a))))
+                .map_or(Step::GetComb, |comb| Step::TransComb(comb.cards))
+        }
+    }
+}
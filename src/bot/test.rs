@@ -0,0 +1,147 @@
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashMap;
+
+    use tokio::sync::mpsc;
+
+    use rand::rngs::mock::StepRng;
+    use rand::thread_rng;
+
+    use crate::bot::*;
+    use crate::game::*;
+
+    fn cards(json: &str) -> HashSet<Card> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    /// The bot with this generator never plays at random.
+    fn rng() -> StepRng {
+        StepRng::new(u64::MAX, 0)
+    }
+
+    #[test]
+    fn bot_test_passive() {
+        assert_eq!(
+            choose_step(
+                &cards(r#"[["2", "♣"], ["K", "♠"], ["2", "♥"]]"#),
+                &State::Passive,
+                &mut rng()
+            ),
+            Step::GiveComb(cards(r#"[["2", "♣"], ["2", "♥"]]"#))
+        );
+        assert_eq!(
+            choose_step(
+                &cards(r#"[["2", "♣"], ["K", "♠"]]"#),
+                &State::Passive,
+                &mut rng()
+            ),
+            Step::GiveComb(cards(r#"[["2", "♣"]]"#))
+        );
+        assert_eq!(
+            choose_step(&HashSet::new(), &State::Passive, &mut rng()),
+            Step::GetCard
+        );
+    }
+
+    #[test]
+    fn bot_test_active() {
+        let comb = cards(r#"[["5", "♦"], ["5", "♠"]]"#);
+        let state = State::Active(Board {
+            comb: Comb::new(comb.clone()).unwrap(),
+            cards: comb,
+        });
+
+        // The weakest pair which beats the board
+        assert_eq!(
+            choose_step(
+                &cards(r#"[["K", "♠"], ["K", "♥"], ["7", "♣"], ["7", "♥"], ["A", "♣"]]"#),
+                &state,
+                &mut rng()
+            ),
+            Step::TransComb(cards(r#"[["7", "♣"], ["7", "♥"]]"#))
+        );
+        // Two pairs made with the board's cards
+        assert_eq!(
+            choose_step(&cards(r#"[["3", "♠"], ["3", "♥"]]"#), &state, &mut rng()),
+            Step::TransComb(cards(r#"[["3", "♠"], ["3", "♥"], ["5", "♦"], ["5", "♠"]]"#))
+        );
+        assert_eq!(
+            choose_step(&cards(r#"[["2", "♠"], ["A", "♥"]]"#), &state, &mut rng()),
+            Step::GetComb
+        );
+    }
+
+    #[tokio::test]
+    async fn bot_test_game() {
+        let (requests, rx) = mpsc::unbounded_channel();
+        let mut events = HashMap::new();
+        let mut clients = HashMap::new();
+        for pid in 1..=3 {
+            let (event_tx, event) = mpsc::unbounded_channel();
+            events.insert(pid, event_tx);
+            clients.insert(pid, GameChannelClient(requests.clone(), pid, event));
+        }
+        let rules = Rules {
+            time_control: TimeControl {
+                turn: 0,
+                ..TimeControl::default()
+            },
+            ..Rules::default()
+        };
+        let worker = tokio::spawn(game_worker(events, rx, 1, rules, None));
+
+        let mut steps = 0;
+        loop {
+            let view = clients[&1].get_view().await.unwrap();
+            if view.winner.is_some() {
+                break;
+            }
+            let client = clients.get_mut(&view.stepping_player).unwrap();
+            let view = client.get_view().await.unwrap();
+            let step = choose_step(&view.cards, &view.state, &mut thread_rng());
+            assert!(client.make_step(step).await.unwrap().result.is_ok());
+            steps += 1;
+            assert!(steps < 10000);
+        }
+
+        let mut exited = Vec::new();
+        for client in clients.into_values() {
+            exited.push(client.exit().await.unwrap());
+        }
+        assert_eq!(exited.iter().filter(|&&last| last).count(), 1);
+        assert!(worker.await.unwrap().winner.is_some());
+
+        // Nothing can be asked of the game after its worker has exited
+        let (requests, rx) = mpsc::unbounded_channel();
+        drop(rx);
+        let client = GameChannelClient(requests, 1, mpsc::unbounded_channel().1);
+        assert_eq!(client.get_view().await.map(|_| ()), Err(WorkerExited));
+    }
+
+    #[tokio::test]
+    async fn bot_test_stopped_game() {
+        let (requests, rx) = mpsc::unbounded_channel();
+        let mut events = HashMap::new();
+        let mut clients = Vec::new();
+        for pid in 1..=2 {
+            let (event_tx, event) = mpsc::unbounded_channel();
+            events.insert(pid, event_tx);
+            clients.push(GameChannelClient(requests.clone(), pid, event));
+        }
+        let worker = tokio::spawn(game_worker(events, rx, 1, Rules::default(), Some(1)));
+
+        let view = clients[0].get_view().await.unwrap();
+        let step = choose_step(&view.cards, &view.state, &mut rng());
+        assert!(clients[0].make_step(step).await.unwrap().result.is_ok());
+        requests.send((1, GameRequest::Stop)).unwrap();
+
+        // The unfinished game is the result, the players learn it has exited
+        let result = worker.await.unwrap();
+        assert_eq!(result.winner, None);
+        assert_eq!(result.steps.len(), 1);
+        let client = &mut clients[1];
+        while client.next_event().await.is_some() {}
+        assert_eq!(client.get_view().await.map(|_| ()), Err(WorkerExited));
+    }
+}
@@ -88,6 +88,27 @@ impl serde::ser::Serialize for CardRank {
     }
 }
 
+impl std::fmt::Display for CardRank {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rank = match *self {
+            CardRank::Two => "2",
+            CardRank::Three => "3",
+            CardRank::Four => "4",
+            CardRank::Five => "5",
+            CardRank::Six => "6",
+            CardRank::Seven => "7",
+            CardRank::Eight => "8",
+            CardRank::Nine => "9",
+            CardRank::Ten => "10",
+            CardRank::Jack => "J",
+            CardRank::Queen => "Q",
+            CardRank::King => "K",
+            CardRank::Ace => "A",
+        };
+        write!(f, "{}", rank)
+    }
+}
+
 pub const CARD_RANKS: [CardRank; 13] = [
     CardRank::Two,
     CardRank::Three,
@@ -159,6 +180,18 @@ impl serde::ser::Serialize for CardSuit {
     }
 }
 
+impl std::fmt::Display for CardSuit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let suit = match *self {
+            CardSuit::Spades => "♠",
+            CardSuit::Clubs => "♣",
+            CardSuit::Diamonds => "♦",
+            CardSuit::Hearts => "♥",
+        };
+        write!(f, "{}", suit)
+    }
+}
+
 pub const CARD_SUITS: [CardSuit; 4] = [
     CardSuit::Spades,
     CardSuit::Clubs,
@@ -172,6 +205,13 @@ pub struct Card {
     pub suit: CardSuit,
 }
 
+/// The card as a rank followed by a suit, e.g. `10♥`.
+impl std::fmt::Display for Card {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.rank, self.suit)
+    }
+}
+
 impl serde::ser::Serialize for Card {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
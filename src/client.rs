@@ -0,0 +1,373 @@
+//! The terminal client's game: the table drawn from server messages and the
+//! commands typed by the player. The `poker-durak-cli` binary does the
+//! networking and the terminal I/O.
+//!
+//! Cards are numbered in the order they are drawn, the hand first and the
+//! board after it, so a command takes card numbers, e.g. `p 1 2` plays the
+//! first two cards of the hand.
+
+use std::collections::{HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::card::Card;
+use crate::comb::Comb;
+use crate::game::Step;
+use crate::i18n::Language;
+use crate::protocol::*;
+
+mod test;
+
+/// How many chat lines and notices are shown.
+const CHAT_LINES: usize = 10;
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Step(StepMessage),
+    Say(String),
+    Exit,
+    Redraw,
+    Help,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CommandError {
+    Unknown,
+    NoCards,
+    /// A card number which isn't on the table.
+    NoSuchCard(String),
+    EmptyMessage,
+}
+
+/// The game as the player sees it.
+pub struct Table {
+    language: Language,
+    pub nickname: String,
+    pub hand: Vec<Card>,
+    pub board: Option<BoardView>,
+    pub deck_size: usize,
+    pub opponents: Vec<SeatView>,
+    pub your_turn: bool,
+    /// Seconds left for the step and when they were counted.
+    time_left: Option<(u64, Instant)>,
+    /// Seconds left on the game clock and when they were counted.
+    clock: Option<(u64, Instant)>,
+    chat: VecDeque<String>,
+    /// `Some(true)` once the player has won, `Some(false)` after a loss.
+    pub result: Option<bool>,
+}
+
+/// Sorts cards by rank, then by suit, so numbers don't jump between draws.
+fn sorted(cards: &HashSet<Card>) -> Vec<Card> {
+    let mut cards = cards.iter().copied().collect::<Vec<_>>();
+    cards.sort_by_key(|card| (card.rank, card.suit as u8));
+    cards
+}
+
+/// Seconds left on the timer rounded up, so a fresh timer shows its full time.
+fn seconds_left(timer: Option<(u64, Instant)>) -> Option<u64> {
+    timer.map(|(seconds, since)| {
+        let left = Duration::from_secs(seconds).saturating_sub(since.elapsed());
+        (left.as_millis() as u64).div_ceil(1000)
+    })
+}
+
+fn format_seconds(seconds: u64) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+fn format_cards(cards: &[Card], first: usize) -> String {
+    cards
+        .iter()
+        .enumerate()
+        .map(|(i, card)| format!("[{}] {}", first + i, card))
+        .collect::<Vec<_>>()
+        .join("  ")
+}
+
+impl Table {
+    pub fn new(language: Language) -> Table {
+        Table {
+            language,
+            nickname: String::new(),
+            hand: Vec::new(),
+            board: None,
+            deck_size: 0,
+            opponents: Vec::new(),
+            your_turn: false,
+            time_left: None,
+            clock: None,
+            chat: VecDeque::new(),
+            result: None,
+        }
+    }
+
+    fn text(&self, key: &str) -> &'static str {
+        self.language.text(key).unwrap_or("?")
+    }
+
+    /// Adds the line to the chat lines and notices.
+    pub fn notice(&mut self, line: String) {
+        self.chat.push_back(line);
+        while self.chat.len() > CHAT_LINES {
+            self.chat.pop_front();
+        }
+    }
+
+    /// Tells what the player did, e.g. the step of a bot in an offline game.
+    pub fn step_notice(&mut self, nickname: &str, step: &Step) {
+        let text = match step {
+            Step::GetCard => self.text("cli.took_card").to_string(),
+            Step::GetComb => self.text("cli.took_comb").to_string(),
+            Step::GiveComb(cards) | Step::TransComb(cards) => format!(
+                "{} {}",
+                Comb::new(cards.clone()).map_or("?", |comb| comb.name()),
+                sorted(cards)
+                    .iter()
+                    .map(|card| card.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+        };
+        self.notice(format!("{}: {}", nickname, text));
+    }
+
+    /// Cards of the board after the hand's ones, in the drawn order.
+    fn board_cards(&self) -> Vec<Card> {
+        match &self.board {
+            Some(board) => sorted(&board.cards),
+            None => Vec::new(),
+        }
+    }
+
+    /// Applies the server message, returns `true` if the table should be
+    /// drawn again.
+    pub fn update(&mut self, message: ServerMessage) -> bool {
+        match message {
+            ServerMessage::Welcome { nickname, .. } => self.nickname = nickname,
+            ServerMessage::Opponents { opponents } => {
+                let names = opponents
+                    .iter()
+                    .map(|opponent| format!("{} ({})", opponent.nickname, opponent.rating))
+                    .collect::<Vec<_>>();
+                self.notice(format!(
+                    "{}: {}",
+                    self.text("cli.opponents"),
+                    names.join(", ")
+                ));
+            }
+            ServerMessage::YourCards { cards, deck_size } => {
+                self.hand = sorted(&cards);
+                self.deck_size = deck_size;
+            }
+            ServerMessage::GameSnapshot {
+                cards,
+                board,
+                deck_size,
+                opponents,
+                your_turn,
+                time_left,
+                clock,
+                ..
+            } => {
+                let now = Instant::now();
+                self.hand = sorted(&cards);
+                self.board = board;
+                self.deck_size = deck_size;
+                self.opponents = opponents;
+                self.your_turn = your_turn;
+                // Zero means the step isn't limited
+                self.time_left = Some((time_left, now)).filter(|&(time_left, _)| time_left > 0);
+                self.clock = clock.map(|clock| (clock, now));
+            }
+            ServerMessage::YourTurn {
+                board,
+                cards,
+                deck_size,
+                time_left,
+                ..
+            } => {
+                self.hand = sorted(&cards);
+                self.board = board;
+                self.deck_size = deck_size;
+                self.your_turn = true;
+                self.time_left =
+                    Some((time_left, Instant::now())).filter(|&(time_left, _)| time_left > 0);
+            }
+            ServerMessage::YouMadeStep {
+                board,
+                cards,
+                deck_size,
+                ..
+            } => {
+                self.hand = sorted(&cards);
+                self.board = board;
+                self.deck_size = deck_size;
+                self.your_turn = false;
+            }
+            ServerMessage::StepError { message, .. } => {
+                self.notice(format!("{}: {}", self.text("cli.error"), message))
+            }
+            ServerMessage::Message { from, text } => self.notice(format!("{}: {}", from, text)),
+            ServerMessage::PlayerLeft { nickname } => {
+                self.notice(format!("{} {}", nickname, self.text("cli.left")))
+            }
+            ServerMessage::OutOfTime { nickname, timeouts } => self.notice(format!(
+                "{} {} ({})",
+                nickname,
+                self.text("cli.out_of_time"),
+                timeouts
+            )),
+            ServerMessage::Sent { ok: false } => {
+                let line = self.text("cli.not_sent").to_string();
+                self.notice(line)
+            }
+            ServerMessage::ServerShutdown { wait } => {
+                self.notice(format!("{} {}", self.text("cli.server_shutdown"), wait))
+            }
+            ServerMessage::GameWinner => self.result = Some(true),
+            ServerMessage::GameLoser => self.result = Some(false),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Parses a command typed by the player, card numbers refer to the drawn
+    /// table.
+    pub fn parse_command(&self, line: &str) -> Result<Command, CommandError> {
+        let line = line.trim();
+        let (command, rest) = match line.find(' ') {
+            Some(pos) => (&line[..pos], line[pos + 1..].trim()),
+            None => (line, ""),
+        };
+
+        match command {
+            "" => Ok(Command::Redraw),
+            "?" | "h" => Ok(Command::Help),
+            "t" => Ok(Command::Step(StepMessage::GetCard)),
+            "g" => Ok(Command::Step(StepMessage::GetComb)),
+            "p" => Ok(Command::Step(StepMessage::GiveComb {
+                cards: self.cards_by_numbers(rest)?,
+            })),
+            "x" => Ok(Command::Step(StepMessage::TransComb {
+                cards: self.cards_by_numbers(rest)?,
+            })),
+            "m" if rest.is_empty() => Err(CommandError::EmptyMessage),
+            "m" => Ok(Command::Say(rest.to_string())),
+            "q" => Ok(Command::Exit),
+            _ => Err(CommandError::Unknown),
+        }
+    }
+
+    fn cards_by_numbers(&self, numbers: &str) -> Result<HashSet<Card>, CommandError> {
+        let table = self
+            .hand
+            .iter()
+            .chain(self.board_cards().iter())
+            .copied()
+            .collect::<Vec<_>>();
+
+        let cards = numbers
+            .split_whitespace()
+            .map(|number| match number.parse::<usize>() {
+                Ok(n) if n >= 1 && n <= table.len() => Ok(table[n - 1]),
+                _ => Err(CommandError::NoSuchCard(number.to_string())),
+            })
+            .collect::<Result<HashSet<_>, _>>()?;
+
+        if cards.is_empty() {
+            Err(CommandError::NoCards)
+        } else {
+            Ok(cards)
+        }
+    }
+
+    pub fn error_text(&self, error: &CommandError) -> String {
+        match error {
+            CommandError::Unknown => self.text("cli.unknown_command").to_string(),
+            CommandError::NoCards => self.text("cli.no_cards").to_string(),
+            CommandError::NoSuchCard(number) => {
+                format!("{}: {}", self.text("cli.no_such_card"), number)
+            }
+            CommandError::EmptyMessage => self.text("cli.empty_message").to_string(),
+        }
+    }
+
+    pub fn help(&self) -> &'static str {
+        self.text("cli.help")
+    }
+
+    /// Draws the table as text, timers are counted down to the moment of
+    /// drawing.
+    pub fn render(&self) -> String {
+        let mut lines = Vec::new();
+
+        let mut status = format!("{} - {}", self.text("common.title"), self.nickname);
+        if self.your_turn {
+            status += &format!(", {}", self.text("cli.your_turn"));
+            if let Some(seconds) = seconds_left(self.time_left) {
+                status += &format!(
+                    " ({} {})",
+                    format_seconds(seconds),
+                    self.text("cli.left_to_step")
+                );
+            }
+        }
+        // The game clock runs during the player's turn only
+        let clock = if self.your_turn {
+            seconds_left(self.clock)
+        } else {
+            self.clock.map(|(seconds, _)| seconds)
+        };
+        if let Some(seconds) = clock {
+            status += &format!(", {}: {}", self.text("cli.clock"), format_seconds(seconds));
+        }
+        lines.push(status);
+
+        for opponent in self.opponents.iter() {
+            let mut line = format!(
+                "{}: {} {}",
+                opponent.nickname,
+                opponent.cards,
+                self.text("cli.cards")
+            );
+            if let Some(clock) = opponent.clock {
+                line += &format!(", {}: {}", self.text("cli.clock"), format_seconds(clock));
+            }
+            if !opponent.playing {
+                line += &format!(", {}", self.text("cli.not_playing"));
+            }
+            lines.push(line);
+        }
+        lines.push(format!("{}: {}", self.text("cli.deck"), self.deck_size));
+
+        match &self.board {
+            Some(board) => {
+                lines.push(format!(
+                    "{}: {} {}",
+                    self.text("cli.comb"),
+                    board.comb_name,
+                    sorted(&board.comb)
+                        .iter()
+                        .map(|card| card.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                ));
+                lines.push(format!(
+                    "{}: {}",
+                    self.text("cli.board"),
+                    format_cards(&self.board_cards(), self.hand.len() + 1)
+                ));
+            }
+            None => lines.push(self.text("cli.passive").to_string()),
+        }
+        lines.push(format!(
+            "{}: {}",
+            self.text("cli.hand"),
+            format_cards(&self.hand, 1)
+        ));
+
+        lines.push(String::new());
+        lines.extend(self.chat.iter().cloned());
+        lines.join("\n")
+    }
+}
@@ -0,0 +1,103 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::card::*;
+    use crate::client::*;
+
+    fn card(rank: CardRank, suit: CardSuit) -> Card {
+        Card { rank, suit }
+    }
+
+    fn table() -> Table {
+        let mut table = Table::new(Language::En);
+        table.update(
+            serde_json::from_str(
+                r#"{"type": "GameSnapshot", "cards": [["K", "♠"], ["2", "♥"], ["2", "♣"]],
+                "board": {"comb": [["5", "♦"]], "comb_name": "HighCard",
+                    "cards": [["5", "♦"], ["3", "♠"]]},
+                "deck_size": 30, "seat": 0, "opponents": [{"seat": 1, "nickname": "bob",
+                    "cards": 5, "playing": true, "clock": 95}],
+                "turn_seat": 0, "your_turn": true, "time_left": 42, "clock": 120,
+                "status": {"kind": "Playing"}}"#,
+            )
+            .unwrap(),
+        );
+        table
+    }
+
+    #[test]
+    fn client_test_render() {
+        let table = table();
+        assert_eq!(
+            table.hand,
+            vec![
+                card(CardRank::Two, CardSuit::Clubs),
+                card(CardRank::Two, CardSuit::Hearts),
+                card(CardRank::King, CardSuit::Spades),
+            ]
+        );
+
+        let text = table.render();
+        assert!(text.contains("[1] 2♣  [2] 2♥  [3] K♠"));
+        assert!(text.contains("[4] 3♠  [5] 5♦"));
+        assert!(text.contains("HighCard 5♦"));
+        assert!(text.contains("bob: 5 cards, clock: 1:35"));
+        assert!(text.contains("clock: 2:00"));
+    }
+
+    #[test]
+    fn client_test_commands() {
+        let mut table = table();
+        assert_eq!(table.parse_command(""), Ok(Command::Redraw));
+        assert_eq!(
+            table.parse_command("t"),
+            Ok(Command::Step(StepMessage::GetCard))
+        );
+        assert_eq!(
+            table.parse_command(" x 1  5 "),
+            Ok(Command::Step(StepMessage::TransComb {
+                cards: [
+                    card(CardRank::Two, CardSuit::Clubs),
+                    card(CardRank::Five, CardSuit::Diamonds)
+                ]
+                .iter()
+                .copied()
+                .collect()
+            }))
+        );
+        assert_eq!(
+            table.parse_command("p 6"),
+            Err(CommandError::NoSuchCard("6".to_string()))
+        );
+        assert_eq!(table.parse_command("p"), Err(CommandError::NoCards));
+        assert_eq!(
+            table.parse_command("m hi there"),
+            Ok(Command::Say("hi there".to_string()))
+        );
+        assert_eq!(table.parse_command("m"), Err(CommandError::EmptyMessage));
+        assert_eq!(table.parse_command("z"), Err(CommandError::Unknown));
+
+        assert!(table.update(ServerMessage::Message {
+            from: "bob".to_string(),
+            text: "hi".to_string(),
+        }));
+        assert!(!table.update(ServerMessage::Pong));
+        assert!(table.render().ends_with("bob: hi"));
+        assert!(table.update(ServerMessage::ServerShutdown { wait: 60 }));
+        assert!(table.render().ends_with(" 60"));
+
+        table.step_notice(
+            "bob",
+            &Step::GiveComb(
+                [
+                    card(CardRank::Ten, CardSuit::Hearts),
+                    card(CardRank::Ten, CardSuit::Spades),
+                ]
+                .iter()
+                .copied()
+                .collect(),
+            ),
+        );
+        assert!(table.render().ends_with("bob: Pair 10♠ 10♥"));
+    }
+}
@@ -7,6 +7,10 @@ use crate::card::*;
 
 use serde::{Deserialize, Serialize};
 
+/// The longest combinations, straights, flushes and full houses, have five
+/// cards.
+const MAX_COMB_SIZE: usize = 5;
+
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Serialize, Deserialize)]
 enum CombRank {
     HighestCard(CardRank),
@@ -52,6 +56,42 @@ impl Comb {
         }
     }
 
+    /// All combinations which can be made of the cards.
+    pub fn all(cards: &[Card]) -> Vec<Comb> {
+        fn collect(cards: &[Card], chosen: &mut Vec<Card>, combs: &mut Vec<Comb>) {
+            if !chosen.is_empty() {
+                if let Some(comb) = Comb::new(chosen.iter().copied().collect()) {
+                    combs.push(comb);
+                }
+            }
+            if chosen.len() < MAX_COMB_SIZE {
+                for (i, &card) in cards.iter().enumerate() {
+                    chosen.push(card);
+                    collect(&cards[i + 1..], chosen, combs);
+                    chosen.pop();
+                }
+            }
+        }
+
+        let mut combs = Vec::new();
+        collect(cards, &mut Vec::new(), &mut combs);
+        combs
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self.rank {
+            CombRank::HighestCard(_) => "HighestCard",
+            CombRank::Pair(_) => "Pair",
+            CombRank::TwoPairs(_) => "TwoPairs",
+            CombRank::Set(_) => "Set",
+            CombRank::Straight(_) => "Straight",
+            CombRank::Flush(_) => "Flush",
+            CombRank::FullHouse(_) => "FullHouse",
+            CombRank::FourOfAKind(_) => "FourOfAKind",
+            CombRank::StraightFlush(_) => "StraightFlush",
+        }
+    }
+
     fn get_rank(cards: &HashSet<Card>) -> Option<CombRank> {
         match Comb::is_straight_flush(cards) {
             Some(x) => Some(CombRank::StraightFlush(x)),
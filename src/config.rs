@@ -0,0 +1,421 @@
+//! Settings in increasing priority: defaults, the TOML file, environment
+//! variables and flags. Rooms are set only in the file.
+
+use std::collections::BTreeMap;
+use std::net::ToSocketAddrs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{ExpiryAction, Rules, TimeControl};
+
+mod test;
+
+pub const DEFAULT_CONFIG_FILE: &str = "poker-durak.toml";
+const ENV_PREFIX: &str = "POKER_DURAK_";
+
+/// Settings which can be given as flags and environment variables.
+const OPTIONS: &[(&str, &str)] = &[
+    ("addr", "address to listen on"),
+    (
+        "host",
+        "public host of the server, the listen address by default",
+    ),
+    ("heartbit-interval", "seconds a client may stay silent"),
+    (
+        "turn-timeout",
+        "seconds a player has for a step, 0 for no limit",
+    ),
+    (
+        "game-clock",
+        "seconds on a player's clock for the whole game, 0 for no clock",
+    ),
+    (
+        "increment",
+        "seconds added to a player's clock after every step",
+    ),
+    (
+        "on-expiry",
+        "forfeit or auto_step when a player runs out of time",
+    ),
+    (
+        "max-timeouts",
+        "timeouts in a row before a player with auto steps forfeits, 0 for never",
+    ),
+    (
+        "ws-closed-wait",
+        "seconds to wait for a disconnected player",
+    ),
+    ("max-message-length", "longest chat message in bytes"),
+    ("log-level", "off, error, warn, info, debug or trace"),
+    ("log-file", "file the log is appended to besides stderr"),
+    (
+        "static-dir",
+        "directory overriding embedded static files and templates",
+    ),
+    ("max-games", "games played at once, 0 for no limit"),
+    (
+        "shutdown-wait",
+        "seconds running games may go on after SIGTERM",
+    ),
+    ("hand-size", "cards dealt to every player at the start"),
+    (
+        "bot-rate-limit",
+        "messages a bot may send per second, 0 for no limit",
+    ),
+];
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub addr: String,
+    pub host: Option<String>,
+    pub heartbit_interval: u64,
+    pub ws_closed_wait: u64,
+    pub max_message_length: usize,
+    pub log_level: String,
+    pub log_file: Option<PathBuf>,
+    pub static_dir: Option<PathBuf>,
+    pub max_games: usize,
+    pub shutdown_wait: u64,
+    pub bot_rate_limit: u32,
+    pub rules: Rules,
+    pub rooms: BTreeMap<String, TimeControl>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            addr: "127.0.0.1:8000".to_string(),
+            host: None,
+            heartbit_interval: 15,
+            ws_closed_wait: 5,
+            max_message_length: 4096,
+            log_level: "info".to_string(),
+            log_file: None,
+            static_dir: None,
+            max_games: 0,
+            shutdown_wait: 60,
+            bot_rate_limit: 10,
+            rules: Rules::default(),
+            rooms: vec![
+                (
+                    "rapid".to_string(),
+                    TimeControl {
+                        turn: 60,
+                        total: 600,
+                        increment: 10,
+                        on_expiry: ExpiryAction::AutoStep,
+                        max_timeouts: 3,
+                    },
+                ),
+                (
+                    "blitz".to_string(),
+                    TimeControl {
+                        turn: 20,
+                        total: 180,
+                        increment: 3,
+                        on_expiry: ExpiryAction::AutoStep,
+                        max_timeouts: 3,
+                    },
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(PathBuf, std::io::Error),
+    Parse(PathBuf, toml::de::Error),
+    UnknownOption(String),
+    MissingValue(String),
+    InvalidValue(String, String),
+    Invalid(&'static str, &'static str),
+    InvalidRoom(String, &'static str),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Read(path, e) => write!(f, "can't read {}: {}", path.display(), e),
+            ConfigError::Parse(path, e) => write!(f, "can't parse {}: {}", path.display(), e),
+            ConfigError::UnknownOption(option) => write!(f, "unknown option {}", option),
+            ConfigError::MissingValue(option) => write!(f, "no value for --{}", option),
+            ConfigError::InvalidValue(option, value) => {
+                write!(f, "invalid value \"{}\" for {}", value, option)
+            }
+            ConfigError::Invalid(option, reason) => write!(f, "{} {}", option, reason),
+            ConfigError::InvalidRoom(room, reason) => write!(f, "room {} {}", room, reason),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// What the server was asked to do.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Run(Config),
+    PrintConfig(Config),
+    Help,
+}
+
+/// Games without time limits could wait for a silent player forever.
+fn is_limited(time_control: &TimeControl) -> bool {
+    time_control.turn > 0 || time_control.total > 0
+}
+
+fn parse<T: FromStr>(option: &str, value: &str) -> Result<T, ConfigError> {
+    value
+        .parse()
+        .map_err(|_| ConfigError::InvalidValue(option.to_string(), value.to_string()))
+}
+
+impl Config {
+    /// The host clients connect to.
+    pub fn host(&self) -> &str {
+        self.host.as_deref().unwrap_or(&self.addr)
+    }
+
+    pub fn heartbit_interval(&self) -> Duration {
+        Duration::from_secs(self.heartbit_interval)
+    }
+
+    /// Rules of games in the room, `None` for the default room.
+    pub fn room_rules(&self, room: Option<&str>) -> Option<Rules> {
+        match room {
+            None => Some(self.rules),
+            Some(room) => self.rooms.get(room).map(|&time_control| Rules {
+                time_control,
+                ..self.rules
+            }),
+        }
+    }
+
+    pub fn ws_closed_wait(&self) -> Duration {
+        Duration::from_secs(self.ws_closed_wait)
+    }
+
+    pub fn shutdown_wait(&self) -> Duration {
+        Duration::from_secs(self.shutdown_wait)
+    }
+
+    pub fn log_level(&self) -> log::LevelFilter {
+        self.log_level.parse().unwrap_or(log::LevelFilter::Info)
+    }
+
+    /// Sets the option named as the flag without dashes, e.g. `turn-timeout`.
+    pub fn set(&mut self, option: &str, value: &str) -> Result<(), ConfigError> {
+        match option {
+            "addr" => self.addr = value.to_string(),
+            "host" => self.host = Some(value.to_string()),
+            "heartbit-interval" => self.heartbit_interval = parse(option, value)?,
+            "turn-timeout" => self.rules.time_control.turn = parse(option, value)?,
+            "game-clock" => self.rules.time_control.total = parse(option, value)?,
+            "increment" => self.rules.time_control.increment = parse(option, value)?,
+            "max-timeouts" => self.rules.time_control.max_timeouts = parse(option, value)?,
+            "on-expiry" => {
+                self.rules.time_control.on_expiry = match value {
+                    "forfeit" => ExpiryAction::Forfeit,
+                    "auto_step" => ExpiryAction::AutoStep,
+                    _ => {
+                        return Err(ConfigError::InvalidValue(
+                            option.to_string(),
+                            value.to_string(),
+                        ))
+                    }
+                }
+            }
+            "ws-closed-wait" => self.ws_closed_wait = parse(option, value)?,
+            "max-message-length" => self.max_message_length = parse(option, value)?,
+            "log-level" => self.log_level = value.to_string(),
+            "log-file" => self.log_file = Some(PathBuf::from(value)),
+            "static-dir" => self.static_dir = Some(PathBuf::from(value)),
+            "max-games" => self.max_games = parse(option, value)?,
+            "shutdown-wait" => self.shutdown_wait = parse(option, value)?,
+            "hand-size" => self.rules.hand_size = parse(option, value)?,
+            "bot-rate-limit" => self.bot_rate_limit = parse(option, value)?,
+            _ => return Err(ConfigError::UnknownOption(option.to_string())),
+        }
+        Ok(())
+    }
+
+    pub fn from_file(path: &Path) -> Result<Config, ConfigError> {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| ConfigError::Read(path.to_path_buf(), e))?;
+        toml::from_str(&text).map_err(|e| ConfigError::Parse(path.to_path_buf(), e))
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let resolved = self.addr.to_socket_addrs().map(|mut addrs| addrs.next());
+        if !matches!(resolved, Ok(Some(_))) {
+            return Err(ConfigError::Invalid("addr", "isn't a socket address"));
+        }
+        if self.host().is_empty() {
+            return Err(ConfigError::Invalid("host", "is empty"));
+        }
+        if self.heartbit_interval == 0 {
+            return Err(ConfigError::Invalid(
+                "heartbit-interval",
+                "must be positive",
+            ));
+        }
+        if !is_limited(&self.rules.time_control) {
+            return Err(ConfigError::Invalid(
+                "turn-timeout",
+                "or game-clock must be positive",
+            ));
+        }
+        for (room, time_control) in &self.rooms {
+            if room.is_empty()
+                || !room
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                return Err(ConfigError::InvalidRoom(
+                    room.clone(),
+                    "must be named with latin letters, digits, - and _",
+                ));
+            }
+            if !is_limited(time_control) {
+                return Err(ConfigError::InvalidRoom(
+                    room.clone(),
+                    "must limit the turn or the game time",
+                ));
+            }
+        }
+        if self.max_message_length == 0 {
+            return Err(ConfigError::Invalid(
+                "max-message-length",
+                "must be positive",
+            ));
+        }
+        if self.log_level.parse::<log::LevelFilter>().is_err() {
+            return Err(ConfigError::Invalid(
+                "log-level",
+                "must be off, error, warn, info, debug or trace",
+            ));
+        }
+        if let Some(dir) = &self.static_dir {
+            if !dir.is_dir() {
+                return Err(ConfigError::Invalid("static-dir", "isn't a directory"));
+            }
+        }
+        // Two players must get their cards
+        if self.rules.hand_size == 0 || self.rules.max_players() <= 2 {
+            return Err(ConfigError::Invalid("hand-size", "doesn't fit the deck"));
+        }
+        Ok(())
+    }
+
+    /// Builds the settings from the command line arguments (without the
+    /// program name) and environment variables given by `env`.
+    pub fn from_args<I, E>(args: I, env: E) -> Result<Command, ConfigError>
+    where
+        I: IntoIterator<Item = String>,
+        E: Fn(&str) -> Option<String>,
+    {
+        let mut flags = Vec::new();
+        let mut positional = Vec::new();
+        let mut config_file = env(&format!("{}CONFIG", ENV_PREFIX)).map(PathBuf::from);
+        let mut print_config = false;
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let flag = match arg.strip_prefix("--") {
+                Some(flag) => flag,
+                None => {
+                    positional.push(arg);
+                    continue;
+                }
+            };
+            let (option, value) = match flag.find('=') {
+                Some(pos) => (&flag[..pos], Some(flag[pos + 1..].to_string())),
+                None => (flag, None),
+            };
+            match option {
+                "help" => return Ok(Command::Help),
+                "print-config" => print_config = true,
+                _ if option != "config" && !OPTIONS.iter().any(|&(o, _)| o == option) => {
+                    return Err(ConfigError::UnknownOption(arg));
+                }
+                _ => {
+                    let value = match value.or_else(|| args.next()) {
+                        Some(value) => value,
+                        None => return Err(ConfigError::MissingValue(option.to_string())),
+                    };
+                    if option == "config" {
+                        config_file = Some(PathBuf::from(value));
+                    } else {
+                        flags.push((option.to_string(), value));
+                    }
+                }
+            }
+        }
+
+        let mut config = match config_file {
+            Some(path) => Config::from_file(&path)?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                Config::from_file(Path::new(DEFAULT_CONFIG_FILE))?
+            }
+            None => Config::default(),
+        };
+
+        for &(option, _) in OPTIONS {
+            let var = format!("{}{}", ENV_PREFIX, option.replace('-', "_").to_uppercase());
+            if let Some(value) = env(&var) {
+                config.set(option, &value)?;
+            }
+        }
+
+        // The address and the host used to be positional arguments
+        let mut positional = positional.into_iter();
+        if let Some(addr) = positional.next() {
+            config.addr = addr;
+        }
+        if let Some(host) = positional.next() {
+            config.host = Some(host);
+        }
+        if let Some(arg) = positional.next() {
+            return Err(ConfigError::UnknownOption(arg));
+        }
+
+        for (option, value) in flags {
+            config.set(&option, &value)?;
+        }
+
+        config.validate()?;
+        if print_config {
+            Ok(Command::PrintConfig(config))
+        } else {
+            Ok(Command::Run(config))
+        }
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string_pretty(self).unwrap()
+    }
+}
+
+pub fn usage() -> String {
+    let mut usage = format!(
+        "Usage: poker-durak [ADDR [HOST]] [OPTIONS]\n\n\
+         Options:\n  --config <file>  settings file, {} by default\n  \
+         --print-config   print the settings and exit\n  \
+         --help           print this message and exit\n",
+        DEFAULT_CONFIG_FILE
+    );
+    for (option, description) in OPTIONS {
+        usage += &format!("  --{} <value>  {}\n", option, description);
+    }
+    usage += &format!(
+        "\nEvery option may also be set with an environment variable, e.g. {}TURN_TIMEOUT.\n",
+        ENV_PREFIX
+    );
+    usage
+}
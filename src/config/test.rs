@@ -0,0 +1,168 @@
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashMap;
+    use std::fs;
+
+    use crate::config::*;
+
+    fn run(args: &[&str], env: &[(&str, &str)]) -> Result<Command, ConfigError> {
+        let env = env
+            .iter()
+            .map(|&(k, v)| (k.to_string(), v.to_string()))
+            .collect::<HashMap<_, _>>();
+        Config::from_args(args.iter().map(|s| s.to_string()), |name| {
+            env.get(name).cloned()
+        })
+    }
+
+    #[test]
+    fn config_test_defaults() {
+        let config = match run(&[], &[]).unwrap() {
+            Command::Run(config) => config,
+            _ => panic!(),
+        };
+        assert_eq!(config, Config::default());
+        assert_eq!(config.host(), "127.0.0.1:8000");
+        assert_eq!(config.rules.time_control.turn, 300);
+        assert_eq!(config.room_rules(None), Some(config.rules));
+        assert_eq!(
+            config
+                .room_rules(Some("blitz"))
+                .unwrap()
+                .time_control
+                .on_expiry,
+            ExpiryAction::AutoStep
+        );
+        assert_eq!(config.room_rules(Some("bullet")), None);
+        assert_eq!(config.log_level(), log::LevelFilter::Info);
+    }
+
+    #[test]
+    fn config_test_priority() {
+        let path = std::env::temp_dir().join(format!("poker-durak-{}.toml", std::process::id()));
+        fs::write(
+            &path,
+            "heartbit_interval = 20\nmax_games = 3\n\n[rules]\nhand_size = 6\n\n\
+             [rules.time_control]\nturn = 60\n\n[rooms.bullet]\nturn = 5\ntotal = 60\n",
+        )
+        .unwrap();
+
+        let command = run(
+            &[
+                "0.0.0.0:80",
+                "--config",
+                path.to_str().unwrap(),
+                "--turn-timeout=90",
+                "--on-expiry",
+                "auto_step",
+                "--shutdown-wait",
+                "10",
+                "--print-config",
+            ],
+            &[
+                ("POKER_DURAK_TURN_TIMEOUT", "30"),
+                ("POKER_DURAK_MAX_GAMES", "10"),
+                ("POKER_DURAK_HOST", "durak.example"),
+            ],
+        );
+        fs::remove_file(&path).unwrap();
+
+        let config = match command.unwrap() {
+            Command::PrintConfig(config) => config,
+            _ => panic!(),
+        };
+        assert_eq!(config.addr, "0.0.0.0:80");
+        assert_eq!(config.host(), "durak.example");
+        assert_eq!(config.rules.time_control.turn, 90);
+        assert_eq!(config.rules.time_control.on_expiry, ExpiryAction::AutoStep);
+        assert_eq!(config.rooms.keys().collect::<Vec<_>>(), vec!["bullet"]);
+        assert_eq!(
+            config
+                .room_rules(Some("bullet"))
+                .unwrap()
+                .time_control
+                .total,
+            60
+        );
+        assert_eq!(config.heartbit_interval, 20);
+        assert_eq!(config.max_games, 10);
+        assert_eq!(config.shutdown_wait(), Duration::from_secs(10));
+        assert_eq!(config.rules.hand_size, 6);
+
+        let printed: Config = toml::from_str(&config.to_toml()).unwrap();
+        assert_eq!(printed, config);
+    }
+
+    #[test]
+    fn config_test_errors() {
+        assert_eq!(run(&["--help", "--bogus"], &[]).unwrap(), Command::Help);
+        assert!(matches!(
+            run(&["--bogus"], &[]),
+            Err(ConfigError::UnknownOption(_))
+        ));
+        assert!(matches!(
+            run(&["--turn-timeout"], &[]),
+            Err(ConfigError::MissingValue(_))
+        ));
+        assert!(matches!(
+            run(&["--max-games", "many"], &[]),
+            Err(ConfigError::InvalidValue(_, _))
+        ));
+        assert!(matches!(
+            run(&[], &[("POKER_DURAK_TURN_TIMEOUT", "-1")]),
+            Err(ConfigError::InvalidValue(_, _))
+        ));
+        assert!(matches!(
+            run(&["--turn-timeout", "0"], &[]),
+            Err(ConfigError::Invalid("turn-timeout", _))
+        ));
+        assert!(matches!(
+            run(&["--turn-timeout", "0", "--game-clock", "300"], &[]),
+            Ok(Command::Run(_))
+        ));
+        assert!(matches!(
+            run(&["--on-expiry", "panic"], &[]),
+            Err(ConfigError::InvalidValue(_, _))
+        ));
+        assert!(matches!(
+            run(&["--log-level", "loud"], &[]),
+            Err(ConfigError::Invalid("log-level", _))
+        ));
+        assert!(matches!(
+            run(&["--hand-size", "26"], &[]),
+            Err(ConfigError::Invalid("hand-size", _))
+        ));
+        assert!(matches!(
+            run(&["--addr", "nowhere"], &[]),
+            Err(ConfigError::Invalid("addr", _))
+        ));
+        assert!(matches!(
+            run(
+                &[],
+                &[("POKER_DURAK_CONFIG", "/nonexistent/poker-durak.toml")]
+            ),
+            Err(ConfigError::Read(_, _))
+        ));
+        assert!(toml::from_str::<Config>("turn_timeot = 5").is_err());
+
+        let mut config = Config::default();
+        config
+            .rooms
+            .insert("slow".to_string(), TimeControl::default());
+        assert!(config.validate().is_ok());
+        config
+            .rooms
+            .insert("bad room".to_string(), TimeControl::default());
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidRoom(_, _))
+        ));
+        config.rooms.remove("bad room");
+        config.rooms.get_mut("slow").unwrap().turn = 0;
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidRoom(_, _))
+        ));
+    }
+}
@@ -1,15 +1,80 @@
-use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use rand::{seq::SliceRandom, thread_rng};
 
 use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot};
 
 use crate::card::*;
 use crate::comb::*;
+use crate::i18n::{Localize, DEFAULT_LANGUAGE};
+
+mod test;
 
 const PLAYERS_CARDS: usize = 5;
+/// Combinations of more cards are too many to list as legal steps.
+pub const MAX_LEGAL_STEPS_CARDS: usize = 20;
+
+pub type PID = usize;
+
+/// What the game worker does when a player runs out of time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExpiryAction {
+    Forfeit,
+    /// See `Game::expiry_step`.
+    AutoStep,
+}
 
-type PID = usize;
+/// Time limits of a game, in seconds. Zero means no limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeControl {
+    pub turn: u64,
+    /// The player's clock for the whole game.
+    pub total: u64,
+    pub increment: u64,
+    pub on_expiry: ExpiryAction,
+    /// Timeouts in a row after which a player with auto steps forfeits.
+    pub max_timeouts: u32,
+}
+
+impl Default for TimeControl {
+    fn default() -> TimeControl {
+        TimeControl {
+            turn: 300,
+            total: 0,
+            increment: 0,
+            on_expiry: ExpiryAction::Forfeit,
+            max_timeouts: 3,
+        }
+    }
+}
+
+/// Rules a game is played by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Rules {
+    pub hand_size: usize,
+    pub time_control: TimeControl,
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules {
+            hand_size: PLAYERS_CARDS,
+            time_control: TimeControl::default(),
+        }
+    }
+}
+
+impl Rules {
+    /// The largest number of players who can get their cards.
+    pub fn max_players(&self) -> usize {
+        NUMBER_OF_CARDS.checked_div(self.hand_size).unwrap_or(0)
+    }
+}
 
 #[derive(PartialEq, Eq, Debug)]
 struct Player {
@@ -77,7 +142,7 @@ impl Deck {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
 pub enum Step {
     GetCard,
     GiveComb(HashSet<Card>),
@@ -85,6 +150,145 @@ pub enum Step {
     GetComb,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggedStep {
+    pub pid: PID,
+    pub step: Step,
+    pub comb: Option<String>,
+    pub taken: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GameResult {
+    pub players: Vec<PID>,
+    pub winner: Option<PID>,
+    pub started: u64,
+    pub finished: u64,
+    pub hands: Vec<(PID, HashSet<Card>)>,
+    pub steps: Vec<LoggedStep>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SeatInfo {
+    pub pid: PID,
+    pub seat: usize,
+    pub cards: usize,
+    pub playing: bool,
+    pub clock: Option<Duration>,
+}
+
+/// Messages to the player are built from this view alone, so that they can't
+/// leak the cards of others.
+#[derive(Debug, Clone)]
+pub struct PlayerView {
+    pub pid: PID,
+    pub cards: HashSet<Card>,
+    pub state: State,
+    pub deck_size: usize,
+    pub seats: Vec<SeatInfo>,
+    pub stepping_player: PID,
+    pub first_player: PID,
+    pub time_left: Option<Duration>,
+    pub winner: Option<PID>,
+}
+
+impl PlayerView {
+    pub fn cards_of(&self, pid: PID) -> usize {
+        self.seats
+            .iter()
+            .find(|seat| seat.pid == pid)
+            .map_or(0, |seat| seat.cards)
+    }
+
+    /// The number of cards of the player sitting after the given one.
+    pub fn next_seat_cards(&self, pid: PID) -> usize {
+        match self.seats.iter().position(|seat| seat.pid == pid) {
+            Some(pos) => self.seats[(pos + 1) % self.seats.len()].cards,
+            None => 0,
+        }
+    }
+
+    pub fn is_my_turn(&self) -> bool {
+        self.winner.is_none() && self.stepping_player == self.pid
+    }
+
+    pub fn is_kicked(&self) -> bool {
+        !self
+            .seats
+            .iter()
+            .any(|seat| seat.pid == self.pid && seat.playing)
+    }
+}
+
+#[derive(Debug)]
+pub struct StepOutcome {
+    pub result: Result<(), StepError>,
+    pub view: PlayerView,
+}
+
+struct Clocks {
+    control: TimeControl,
+    /// Empty if the total time is unlimited.
+    left: HashMap<PID, Duration>,
+    turn_started: Instant,
+}
+
+impl Clocks {
+    fn new(control: TimeControl, players: &[PID]) -> Clocks {
+        let left = if control.total > 0 {
+            players
+                .iter()
+                .map(|&pid| (pid, Duration::from_secs(control.total)))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        Clocks {
+            control,
+            left,
+            turn_started: Instant::now(),
+        }
+    }
+
+    fn step_made(&mut self, pid: PID) {
+        let elapsed = self.turn_started.elapsed();
+        if let Some(left) = self.left.get_mut(&pid) {
+            *left = left.saturating_sub(elapsed) + Duration::from_secs(self.control.increment);
+        }
+        self.turn_started = Instant::now();
+    }
+
+    /// Starts the turn without charging anybody, e.g. after a player left.
+    fn restart_turn(&mut self) {
+        self.turn_started = Instant::now();
+    }
+
+    /// The clock runs during the player's turn.
+    fn clock(&self, pid: PID, stepping_player: PID) -> Option<Duration> {
+        let left = *self.left.get(&pid)?;
+        if pid == stepping_player {
+            Some(left.saturating_sub(self.turn_started.elapsed()))
+        } else {
+            Some(left)
+        }
+    }
+
+    /// Until the turn time or the clock runs out, whichever is first.
+    fn time_left(&self, stepping_player: PID) -> Option<Duration> {
+        let turn = if self.control.turn > 0 {
+            Some(Duration::from_secs(self.control.turn).saturating_sub(self.turn_started.elapsed()))
+        } else {
+            None
+        };
+
+        match (turn, self.clock(stepping_player, stepping_player)) {
+            (Some(turn), Some(clock)) => Some(turn.min(clock)),
+            (turn, clock) => turn.or(clock),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Game {
     players: Vec<Player>,
@@ -92,12 +296,14 @@ pub struct Game {
     players_next: Vec<usize>,
     players_map: HashMap<PID, usize>,
     stepping_player: usize,
+    first_player: usize,
     winner: Option<usize>,
     deck: Deck,
     state: State,
+    hand_size: usize,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Copy, Serialize)]
 pub enum StepError {
     InvalidPID,
     InvalidStepType,
@@ -108,21 +314,15 @@ pub enum StepError {
 
 impl std::fmt::Display for StepError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match &self {
-            StepError::InvalidPID => write!(f, "Вы не можете соверщить шаг сейчас"),
-            StepError::InvalidStepType => write!(f, "Вы не имеете права делать данный тип шага"),
-            StepError::InvalidCards => write!(f, "У вас нет карт, чтобы сделать этот шаг"),
-            StepError::InvalidComb => write!(f, "Ваши карты не являются покерной комбинацией"),
-            StepError::WeakComb => write!(f, "Ваша комбинация слишком слаба"),
-        }
+        write!(f, "{}", self.localize(DEFAULT_LANGUAGE))
     }
 }
 
 impl std::error::Error for StepError {}
 
 impl Game {
-    pub fn new(players_ids: Vec<PID>) -> Option<Game> {
-        if players_ids.len() < NUMBER_OF_CARDS / PLAYERS_CARDS {
+    pub fn new(players_ids: Vec<PID>, rules: Rules) -> Option<Game> {
+        if rules.hand_size > 0 && players_ids.len() < rules.max_players() {
             let mut players = players_ids
                 .iter()
                 .map(|id| Player {
@@ -146,7 +346,7 @@ impl Game {
             players.iter_mut().for_each(|player| {
                 player.cards = player
                     .cards
-                    .union(&deck.get_cards(PLAYERS_CARDS))
+                    .union(&deck.get_cards(rules.hand_size))
                     .copied()
                     .collect()
             });
@@ -157,18 +357,32 @@ impl Game {
             Some(Game {
                 players,
                 stepping_player,
+                first_player: stepping_player,
                 players_prev,
                 players_next,
                 players_map,
                 winner: None,
                 deck,
                 state,
+                hand_size: rules.hand_size,
             })
         } else {
             None
         }
     }
 
+    /// Must be called before the first step.
+    fn set_first_player(&mut self, pid: PID) {
+        if let Some(&player) = self.players_map.get(&pid) {
+            self.stepping_player = player;
+            self.first_player = player;
+        }
+    }
+
+    fn get_first_player(&self) -> PID {
+        self.players[self.first_player].id
+    }
+
     fn win_player(&mut self, pid: PID) {
         let player = self.players_map[&pid];
 
@@ -220,11 +434,11 @@ impl Game {
             let player = self.get_stepping_player();
             let number_of_cards = self.players[self.players_map[&player]].cards.len();
 
-            if number_of_cards < 5 {
+            if number_of_cards < self.hand_size {
                 self.players[self.players_map[&player]].cards = self.players
                     [self.players_map[&player]]
                     .cards
-                    .union(&self.deck.get_cards(5 - number_of_cards))
+                    .union(&self.deck.get_cards(self.hand_size - number_of_cards))
                     .cloned()
                     .collect();
             }
@@ -348,16 +562,6 @@ impl Game {
         }
     }
 
-    fn players_decks(&self) -> Vec<usize> {
-        (1..self.players.len())
-            .map(|i| {
-                self.players[(self.stepping_player + i) % self.players.len()]
-                    .cards
-                    .len()
-            })
-            .collect()
-    }
-
     fn kick_player(&mut self, pid: PID) {
         let player = self.players_map[&pid];
 
@@ -399,221 +603,420 @@ impl Game {
     fn get_state_cards(&self) -> State {
         self.state.clone()
     }
-}
-
-type GameChannelServer = std::sync::mpsc::Sender<GameResponse>;
 
-pub struct GameChannelClient(
-    pub std::sync::mpsc::Sender<(PID, GameRequest)>,
-    pub std::sync::mpsc::Receiver<GameResponse>,
-    pub PID,
-);
-
-#[derive(Debug)]
-pub enum GameRequest {
-    MakeStep(Step),
-    GetPlayersDecks,
-    KickPlayer,
-    GetSteppingPlayer,
-    GetPlayerCards(PID),
-    GetDeckSize,
-    IsPlayerKicked,
-    GetGameWinner,
-    GetState,
-    SendMessage(String),
-    GetMessage,
-    Exit,
-}
+    fn get_players(&self) -> Vec<PID> {
+        self.players.iter().map(|player| player.id).collect()
+    }
 
-#[derive(Debug)]
-pub enum GameResponse {
-    YouMadeStep(Result<(), StepError>),
-    PlayersDecks(Vec<usize>),
-    SteppingPlayer(PID),
-    YourCards(HashSet<Card>),
-    DeckSize(usize),
-    PlayerKicked(bool),
-    GameWinner(Option<PID>),
-    GameState(State),
-    YourMessages(VecDeque<String>),
-    Exited(bool),
-}
+    fn seats(&self, clocks: &Clocks) -> Vec<SeatInfo> {
+        self.players
+            .iter()
+            .enumerate()
+            .map(|(seat, player)| SeatInfo {
+                pid: player.id,
+                seat,
+                cards: player.cards.len(),
+                playing: !self.is_player_kicked(player.id),
+                clock: clocks.clock(player.id, self.get_stepping_player()),
+            })
+            .collect()
+    }
 
-impl GameChannelClient {
-    pub fn exit(self) -> bool {
-        self.0.send((self.2, GameRequest::Exit)).unwrap();
-        match self.1.recv().unwrap() {
-            GameResponse::Exited(f) => f,
-            _ => panic!(),
+    fn player_view(&self, pid: PID, clocks: &Clocks) -> PlayerView {
+        PlayerView {
+            pid,
+            cards: self.get_player_cards(pid),
+            state: self.get_state_cards(),
+            deck_size: self.get_deck_size(),
+            seats: self.seats(clocks),
+            stepping_player: self.get_stepping_player(),
+            first_player: self.get_first_player(),
+            time_left: clocks.time_left(self.get_stepping_player()),
+            winner: self.game_winner(),
         }
     }
 
-    pub fn make_step(&mut self, step: Step) -> Result<(), StepError> {
-        self.0.send((self.2, GameRequest::MakeStep(step))).unwrap();
-        match self.1.recv().unwrap() {
-            GameResponse::YouMadeStep(res) => res,
-            _ => panic!(),
+    fn spectator_view(
+        &self,
+        hands_history: &[Vec<(PID, HashSet<Card>)>],
+        reveal_delay: Option<usize>,
+        clocks: &Clocks,
+    ) -> SpectatorView {
+        let steps = hands_history.len() - 1;
+        SpectatorView {
+            state: self.get_state_cards(),
+            deck_size: self.get_deck_size(),
+            seats: self.seats(clocks),
+            stepping_player: self.get_stepping_player(),
+            winner: self.game_winner(),
+            steps,
+            hands: reveal_delay
+                .filter(|&delay| steps >= delay)
+                .map(|delay| hands_history[steps - delay].clone()),
         }
     }
 
-    pub fn players_decks(&self) -> Vec<usize> {
-        self.0.send((self.2, GameRequest::GetPlayersDecks)).unwrap();
-        match self.1.recv().unwrap() {
-            GameResponse::PlayersDecks(res) => res,
-            _ => panic!(),
+    /// Takes a card, or plays the lowest one if the deck is empty, or takes
+    /// the combination.
+    fn expiry_step(&self) -> Option<Step> {
+        match &self.state {
+            State::Passive if self.get_deck_size() > 0 => Some(Step::GetCard),
+            State::Passive => self.players[self.stepping_player]
+                .cards
+                .iter()
+                .min_by_key(|card| card.rank)
+                .map(|&card| Step::GiveComb(std::iter::once(card).collect())),
+            State::Active(_) => Some(Step::GetComb),
         }
     }
 
-    pub fn kick_me(&mut self) {
-        self.0.send((self.2, GameRequest::KickPlayer)).unwrap();
+    fn get_hands(&self) -> Vec<(PID, HashSet<Card>)> {
+        self.players
+            .iter()
+            .map(|player| (player.id, player.cards.clone()))
+            .collect()
     }
+}
 
-    pub fn get_stepping_player(&self) -> usize {
-        self.0
-            .send((self.2, GameRequest::GetSteppingPlayer))
-            .unwrap();
-        match self.1.recv().unwrap() {
-            GameResponse::SteppingPlayer(pid) => pid,
-            _ => panic!(),
+/// Every step the stepping player with the cards can make, `None` if more than
+/// `MAX_LEGAL_STEPS_CARDS` cards can be combined.
+pub fn legal_steps(cards: &HashSet<Card>, state: &State, deck_size: usize) -> Option<Vec<Step>> {
+    let steps = match state {
+        State::Passive => {
+            let hand = cards.iter().copied().collect::<Vec<_>>();
+            if hand.len() > MAX_LEGAL_STEPS_CARDS {
+                return None;
+            }
+            let take = if deck_size > 0 {
+                Some(Step::GetCard)
+            } else {
+                None
+            };
+            take.into_iter()
+                .chain(
+                    Comb::all(&hand)
+                        .into_iter()
+                        .map(|comb| Step::GiveComb(comb.cards)),
+                )
+                .collect()
         }
+        State::Active(board) => {
+            // A combination is passed with at least one card of the player
+            let table = cards.union(&board.cards).copied().collect::<Vec<_>>();
+            if table.len() > MAX_LEGAL_STEPS_CARDS {
+                return None;
+            }
+            std::iter::once(Step::GetComb)
+                .chain(
+                    Comb::all(&table)
+                        .into_iter()
+                        .filter(|comb| *comb > board.comb && !comb.cards.is_disjoint(cards))
+                        .map(|comb| Step::TransComb(comb.cards)),
+                )
+                .collect()
+        }
+    };
+    Some(steps)
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0)
+}
+
+type GameEventSender = mpsc::UnboundedSender<GameEvent>;
+pub type SpectatorEventSender = mpsc::UnboundedSender<SpectatorEvent>;
+pub type Reply<T> = oneshot::Sender<T>;
+
+pub struct GameChannelClient(
+    pub mpsc::UnboundedSender<(PID, GameRequest)>,
+    pub PID,
+    pub mpsc::UnboundedReceiver<GameEvent>,
+);
+
+/// The game worker has exited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorkerExited;
+
+impl std::fmt::Display for WorkerExited {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the game worker has exited")
     }
+}
 
-    pub fn get_my_cards(&self) -> HashSet<Card> {
-        self.0
-            .send((self.2, GameRequest::GetPlayerCards(self.2)))
-            .unwrap();
-        match self.1.recv().unwrap() {
-            GameResponse::YourCards(cards) => cards,
-            _ => panic!(),
-        }
+impl std::error::Error for WorkerExited {}
+
+/// Pushed to every player as soon as they happen.
+#[derive(Debug, Clone)]
+pub enum GameEvent {
+    StepMade(PID),
+    CardsDealt,
+    Message(PID, String),
+    PlayerLeft(PID),
+    /// Sent before the step made for the player or before the player leaves.
+    OutOfTime(PID, u32),
+    GameOver(Option<PID>),
+}
+
+#[derive(Debug, Clone)]
+pub struct SpectatorView {
+    pub state: State,
+    pub deck_size: usize,
+    pub seats: Vec<SeatInfo>,
+    pub stepping_player: PID,
+    pub winner: Option<PID>,
+    pub steps: usize,
+    /// Hands as they were `reveal_delay` steps ago.
+    pub hands: Option<Vec<(PID, HashSet<Card>)>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum SpectatorEvent {
+    View(SpectatorView),
+    Message(PID, String),
+    PlayerLeft(PID),
+}
+
+/// A request with an answer carries the channel to send it to.
+#[derive(Debug)]
+pub enum GameRequest {
+    MakeStep(Step, Reply<StepOutcome>),
+    KickPlayer,
+    /// Everything a message to the player needs is answered at once.
+    GetView(Reply<PlayerView>),
+    /// Hands are revealed with the given delay in steps.
+    Watch(SpectatorEventSender, Option<usize>),
+    SendMessage(String),
+    /// The answer is `true` for the last player leaving.
+    Exit(Reply<bool>),
+    /// Ends the game unfinished if nobody has won yet.
+    Stop,
+}
+
+impl GameChannelClient {
+    fn send(&self, request: GameRequest) -> Result<(), WorkerExited> {
+        self.0.send((self.1, request)).map_err(|_| WorkerExited)
     }
 
-    pub fn get_another_number_of_cards(&self, pid: PID) -> usize {
-        self.0
-            .send((self.2, GameRequest::GetPlayerCards(pid)))
-            .unwrap();
-        match self.1.recv().unwrap() {
-            GameResponse::YourCards(cards) => cards.len(),
-            _ => panic!(),
-        }
+    async fn ask<T>(
+        &self,
+        request: impl FnOnce(Reply<T>) -> GameRequest,
+    ) -> Result<T, WorkerExited> {
+        let (reply, answer) = oneshot::channel();
+        self.send(request(reply))?;
+        answer.await.map_err(|_| WorkerExited)
     }
 
-    pub fn get_deck_size(&self) -> usize {
-        self.0.send((self.2, GameRequest::GetDeckSize)).unwrap();
-        match self.1.recv().unwrap() {
-            GameResponse::DeckSize(size) => size,
-            _ => panic!(),
-        }
+    pub async fn exit(self) -> Result<bool, WorkerExited> {
+        self.ask(GameRequest::Exit).await
     }
 
-    pub fn is_me_kicked(&self) -> bool {
-        self.0
-            .send((self.2, (GameRequest::IsPlayerKicked)))
-            .unwrap();
-        match self.1.recv().unwrap() {
-            GameResponse::PlayerKicked(f) => f,
-            _ => panic!(),
-        }
+    /// The outcome has the game as it is right after the step.
+    pub async fn make_step(&mut self, step: Step) -> Result<StepOutcome, WorkerExited> {
+        self.ask(|reply| GameRequest::MakeStep(step, reply)).await
     }
 
-    pub fn game_winner(&self) -> Option<PID> {
-        self.0.send((self.2, (GameRequest::GetGameWinner))).unwrap();
-        match self.1.recv().unwrap() {
-            GameResponse::GameWinner(winner) => winner,
-            _ => panic!(),
-        }
+    pub fn kick_me(&mut self) -> Result<(), WorkerExited> {
+        self.send(GameRequest::KickPlayer)
     }
 
-    pub fn get_state_cards(&self) -> State {
-        self.0.send((self.2, GameRequest::GetState)).unwrap();
-        match self.1.recv().unwrap() {
-            GameResponse::GameState(state) => state,
-            _ => panic!(),
-        }
+    pub async fn get_view(&self) -> Result<PlayerView, WorkerExited> {
+        self.ask(GameRequest::GetView).await
     }
 
-    pub fn get_messages(&self) -> VecDeque<String> {
-        self.0.send((self.2, GameRequest::GetMessage)).unwrap();
-        match self.1.recv().unwrap() {
-            GameResponse::YourMessages(msg) => msg,
-            _ => panic!(),
-        }
+    pub fn send_message(&self, msg: String) -> Result<(), WorkerExited> {
+        self.send(GameRequest::SendMessage(msg))
     }
 
-    pub fn send_message(&self, msg: String) {
-        self.0
-            .send((self.2, GameRequest::SendMessage(msg)))
-            .unwrap();
+    /// `None` once the game worker has exited.
+    pub async fn next_event(&mut self) -> Option<GameEvent> {
+        self.2.recv().await
     }
 }
 
-pub fn game_worker(
-    players: HashMap<PID, GameChannelServer>,
-    rx: std::sync::mpsc::Receiver<(PID, GameRequest)>,
+/// Runs until every player has left or the game is stopped.
+pub async fn game_worker(
+    events: HashMap<PID, GameEventSender>,
+    mut rx: mpsc::UnboundedReceiver<(PID, GameRequest)>,
     gid: usize,
-) {
-    let mut playing = players.keys().map(|x| (x, true)).collect::<HashMap<_, _>>();
-    let mut count = players.len();
-    let mut game = Game::new(players.keys().copied().collect()).unwrap();
-    let mut messages = players
-        .keys()
-        .map(|x| (*x, VecDeque::new()))
-        .collect::<HashMap<_, _>>();
+    rules: Rules,
+    first_player: Option<PID>,
+) -> GameResult {
+    let mut playing = events.keys().map(|x| (x, true)).collect::<HashMap<_, _>>();
+    let mut count = events.len();
+    let mut game = Game::new(events.keys().copied().collect(), rules).unwrap();
+    if let Some(pid) = first_player {
+        game.set_first_player(pid);
+    }
+    let mut game_over = false;
+    let mut stepping_player = game.get_stepping_player();
+    let mut clocks = Clocks::new(rules.time_control, &game.get_players());
+    let mut timeouts: HashMap<PID, u32> = HashMap::new();
+    let mut spectators: Vec<(SpectatorEventSender, Option<usize>)> = Vec::new();
+    let mut hands_history = vec![game.get_hands()];
+    let broadcast = |event: GameEvent, except: Option<PID>| {
+        for (id, tx) in events.iter() {
+            if Some(*id) != except {
+                tx.send(event.clone()).ok();
+            }
+        }
+    };
+    let mut result = GameResult {
+        started: unix_time(),
+        hands: game.get_hands(),
+        ..GameResult::default()
+    };
     info!("GAME {} started", gid);
     'outer: loop {
-        match rx.recv() {
-            Ok(req) => {
+        let received = match clocks.time_left(stepping_player) {
+            Some(time_left) if !game_over => tokio::time::timeout(time_left, rx.recv()).await,
+            _ => Ok(rx.recv().await),
+        };
+        // A player who ran out of time makes a request without waiting for the response
+        let (auto, received) = match received {
+            Err(_) => {
+                let control = rules.time_control;
+                let count = timeouts.entry(stepping_player).or_insert(0);
+                *count += 1;
+                info!(
+                    "GAME {} player {} is out of time {} times in a row",
+                    gid, stepping_player, count
+                );
+                broadcast(GameEvent::OutOfTime(stepping_player, *count), None);
+                let forfeit = control.on_expiry == ExpiryAction::Forfeit
+                    || (control.max_timeouts > 0 && *count >= control.max_timeouts);
+                let (reply, _) = oneshot::channel();
+                let req = match game.expiry_step() {
+                    Some(step) if !forfeit => GameRequest::MakeStep(step, reply),
+                    _ => GameRequest::KickPlayer,
+                };
+                (true, Some((stepping_player, req)))
+            }
+            Ok(received) => (false, received),
+        };
+        match received {
+            Some(req) => {
                 let (pid, req) = req;
-                (match req {
-                    GameRequest::MakeStep(step) => {
-                        Some(GameResponse::YouMadeStep(game.make_step(pid, step)))
-                    }
-                    GameRequest::GetPlayersDecks => {
-                        Some(GameResponse::PlayersDecks(game.players_decks()))
+                let mut changed = false;
+                let mut step_made = false;
+                // The step is answered once the clocks count it
+                let mut step_reply = None;
+                match req {
+                    GameRequest::MakeStep(step, reply) => {
+                        let taken = match (&step, game.get_state_cards()) {
+                            (Step::GetComb, State::Active(board)) => board.comb.cards.len(),
+                            _ => 0,
+                        };
+                        let res = game.make_step(pid, step.clone());
+                        if res.is_ok() {
+                            let comb = match (&step, game.get_state_cards()) {
+                                (Step::GiveComb(_), State::Active(board))
+                                | (Step::TransComb(_), State::Active(board)) => {
+                                    Some(board.comb.name().to_string())
+                                }
+                                _ => None,
+                            };
+                            let dealt = matches!(step, Step::GetComb);
+                            result.steps.push(LoggedStep {
+                                pid,
+                                step,
+                                comb,
+                                taken,
+                            });
+                            hands_history.push(game.get_hands());
+                            changed = true;
+                            step_made = true;
+                            if !auto {
+                                timeouts.remove(&pid);
+                            }
+                            broadcast(GameEvent::StepMade(pid), None);
+                            if dealt {
+                                broadcast(GameEvent::CardsDealt, None);
+                            }
+                        }
+                        step_reply = Some((reply, res));
                     }
                     GameRequest::KickPlayer => {
-                        game.kick_player(pid);
-                        None
-                    }
-                    GameRequest::GetSteppingPlayer => {
-                        Some(GameResponse::SteppingPlayer(game.get_stepping_player()))
-                    }
-                    GameRequest::GetPlayerCards(pid) => {
-                        Some(GameResponse::YourCards(game.get_player_cards(pid)))
+                        if !game.is_player_kicked(pid) {
+                            game.kick_player(pid);
+                            changed = true;
+                            broadcast(GameEvent::PlayerLeft(pid), Some(pid));
+                            spectators
+                                .retain(|(tx, _)| tx.send(SpectatorEvent::PlayerLeft(pid)).is_ok());
+                        }
                     }
-                    GameRequest::GetDeckSize => Some(GameResponse::DeckSize(game.get_deck_size())),
-                    GameRequest::IsPlayerKicked => {
-                        Some(GameResponse::PlayerKicked(game.is_player_kicked(pid)))
+                    GameRequest::GetView(reply) => {
+                        reply.send(game.player_view(pid, &clocks)).ok();
                     }
-                    GameRequest::GetGameWinner => {
-                        Some(GameResponse::GameWinner(game.game_winner()))
+                    GameRequest::Watch(tx, reveal_delay) => {
+                        if tx
+                            .send(SpectatorEvent::View(game.spectator_view(
+                                &hands_history,
+                                reveal_delay,
+                                &clocks,
+                            )))
+                            .is_ok()
+                        {
+                            spectators.push((tx, reveal_delay));
+                        }
                     }
-                    GameRequest::GetState => Some(GameResponse::GameState(game.get_state_cards())),
-                    GameRequest::Exit => {
-                        game.kick_player(pid);
+                    GameRequest::Exit(reply) => {
+                        if !game.is_player_kicked(pid) {
+                            game.kick_player(pid);
+                            changed = true;
+                            broadcast(GameEvent::PlayerLeft(pid), Some(pid));
+                            spectators
+                                .retain(|(tx, _)| tx.send(SpectatorEvent::PlayerLeft(pid)).is_ok());
+                        }
                         *playing.get_mut(&pid).unwrap() = false;
                         count -= 1;
+                        reply.send(count == 0).ok();
                         if count == 0 {
-                            players[&pid].send(GameResponse::Exited(true)).unwrap();
                             break 'outer;
                         }
-                        Some(GameResponse::Exited(false))
                     }
-                    GameRequest::SendMessage(msg) => {
-                        for (id, msgs) in messages.iter_mut() {
-                            if id != &pid {
-                                msgs.push_back(msg.clone());
-                            }
-                        }
-                        None
+                    GameRequest::Stop => {
+                        info!("GAME {} stopped", gid);
+                        break 'outer;
                     }
-                    GameRequest::GetMessage => {
-                        let t = GameResponse::YourMessages(messages.remove(&pid).unwrap());
-                        messages.insert(pid, VecDeque::new());
-                        Some(t)
+                    GameRequest::SendMessage(msg) => {
+                        spectators.retain(|(tx, _)| {
+                            tx.send(SpectatorEvent::Message(pid, msg.clone())).is_ok()
+                        });
+                        broadcast(GameEvent::Message(pid, msg), Some(pid));
                     }
-                })
-                .map_or((), |resp| players[&pid].send(resp).unwrap())
+                }
+
+                // The turn restarts even if the player out of time couldn't step
+                if step_made || auto {
+                    clocks.step_made(pid);
+                } else if stepping_player != game.get_stepping_player() {
+                    clocks.restart_turn();
+                }
+                stepping_player = game.get_stepping_player();
+
+                if let Some((reply, result)) = step_reply {
+                    let view = game.player_view(pid, &clocks);
+                    reply.send(StepOutcome { result, view }).ok();
+                }
+
+                if changed {
+                    spectators.retain(|(tx, reveal_delay)| {
+                        tx.send(SpectatorEvent::View(game.spectator_view(
+                            &hands_history,
+                            *reveal_delay,
+                            &clocks,
+                        )))
+                        .is_ok()
+                    });
+                }
+
+                if !game_over && game.game_winner().is_some() {
+                    game_over = true;
+                    broadcast(GameEvent::GameOver(game.game_winner()), None);
+                }
             }
             _ => {
                 break 'outer;
@@ -621,4 +1024,10 @@ pub fn game_worker(
         }
     }
     info!("GAME {} exiting", gid);
+    GameResult {
+        players: game.get_players(),
+        winner: game.game_winner(),
+        finished: unix_time(),
+        ..result
+    }
 }
@@ -0,0 +1,122 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::game::*;
+
+    #[test]
+    fn game_test_clocks() {
+        let control = TimeControl {
+            turn: 5,
+            total: 10,
+            increment: 2,
+            on_expiry: ExpiryAction::AutoStep,
+            max_timeouts: 0,
+        };
+        let mut clocks = Clocks::new(control, &[1, 2]);
+
+        assert_eq!(clocks.clock(2, 1), Some(Duration::from_secs(10)));
+        assert!(clocks.time_left(1).unwrap() <= Duration::from_secs(5));
+
+        clocks.step_made(1);
+        let clock = clocks.clock(1, 2).unwrap();
+        assert!(clock > Duration::from_secs(11) && clock <= Duration::from_secs(12));
+
+        clocks.left.insert(2, Duration::from_secs(3));
+        assert!(clocks.time_left(2).unwrap() <= Duration::from_secs(3));
+
+        let clocks = Clocks::new(TimeControl::default(), &[1, 2]);
+        assert_eq!(clocks.clock(1, 1), None);
+        assert!(clocks.time_left(1).unwrap() > Duration::from_secs(299));
+    }
+
+    #[test]
+    fn game_test_player_view() {
+        let game = Game::new(vec![1, 2, 3], Rules::default()).unwrap();
+        let clocks = Clocks::new(TimeControl::default(), &[1, 2, 3]);
+        let view = game.player_view(2, &clocks);
+
+        assert_eq!(view.pid, 2);
+        assert_eq!(view.cards, game.get_player_cards(2));
+        assert_eq!(view.cards_of(3), game.get_player_cards(3).len());
+        assert_eq!(view.next_seat_cards(3), game.get_player_cards(1).len());
+        assert_eq!(view.cards_of(4), 0);
+        assert_eq!(view.is_my_turn(), game.get_stepping_player() == 2);
+        assert!(!view.is_kicked());
+    }
+
+    #[test]
+    fn game_test_hand_size() {
+        for hand_size in [3, 7] {
+            let rules = Rules {
+                hand_size,
+                ..Rules::default()
+            };
+            let mut game = Game::new(vec![1, 2], rules).unwrap();
+            game.set_first_player(1);
+            assert_eq!(game.get_player_cards(1).len(), hand_size);
+
+            // The taker's opponent is filled up to the hand size and gets one more card
+            let card = *game.get_player_cards(1).iter().next().unwrap();
+            let comb = [card].iter().copied().collect();
+            assert!(game.make_step(1, Step::GiveComb(comb)).is_ok());
+            assert!(game.make_step(2, Step::GetComb).is_ok());
+            assert_eq!(game.get_player_cards(1).len(), hand_size + 1);
+            assert_eq!(game.get_player_cards(2).len(), hand_size + 1);
+        }
+    }
+
+    #[test]
+    fn game_test_legal_steps() {
+        let cards = |json| serde_json::from_str::<HashSet<Card>>(json).unwrap();
+
+        let hand = cards(r#"[["2", "♣"], ["2", "♥"], ["K", "♠"]]"#);
+        let steps = legal_steps(&hand, &State::Passive, 10).unwrap();
+        assert_eq!(steps.len(), 5);
+        assert!(steps.contains(&Step::GetCard));
+        assert!(steps.contains(&Step::GiveComb(cards(r#"[["2", "♣"], ["2", "♥"]]"#))));
+        let steps = legal_steps(&hand, &State::Passive, 0).unwrap();
+        assert!(!steps.contains(&Step::GetCard));
+
+        let comb = cards(r#"[["5", "♦"], ["5", "♠"]]"#);
+        let state = State::Active(Board {
+            comb: Comb::new(comb.clone()).unwrap(),
+            cards: comb,
+        });
+        let hand = cards(r#"[["3", "♠"], ["3", "♥"], ["7", "♣"]]"#);
+        assert_eq!(
+            legal_steps(&hand, &state, 10).unwrap(),
+            vec![
+                Step::GetComb,
+                Step::TransComb(cards(r#"[["3", "♠"], ["3", "♥"], ["5", "♦"], ["5", "♠"]]"#))
+            ]
+        );
+    }
+
+    #[test]
+    fn game_test_legal_steps_of_large_hand() {
+        let deck = CARD_RANKS
+            .iter()
+            .flat_map(|&rank| CARD_SUITS.iter().map(move |&suit| Card { rank, suit }))
+            .collect::<Vec<_>>();
+        let hand = |cards: &[Card]| cards.iter().copied().collect::<HashSet<_>>();
+
+        assert_eq!(legal_steps(&hand(&deck), &State::Passive, 0), None);
+        let steps = legal_steps(&hand(&deck[..MAX_LEGAL_STEPS_CARDS]), &State::Passive, 0);
+        assert!(steps
+            .unwrap()
+            .iter()
+            .all(|step| matches!(step, Step::GiveComb(_))));
+
+        // The cards on the board count too
+        let board = hand(&deck[..2]);
+        let state = State::Active(Board {
+            comb: Comb::new(board.clone()).unwrap(),
+            cards: board,
+        });
+        assert!(legal_steps(&hand(&deck[2..MAX_LEGAL_STEPS_CARDS]), &state, 0).is_some());
+        assert_eq!(
+            legal_steps(&hand(&deck[2..=MAX_LEGAL_STEPS_CARDS]), &state, 0),
+            None
+        );
+    }
+}
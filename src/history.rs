@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::game::{GameResult, Step, PID};
+use crate::journal;
+
+mod test;
+
+const HISTORY_FILE: &str = "history.jsonl";
+
+#[derive(Default)]
+pub struct History {
+    games: Vec<GameResult>,
+}
+
+#[derive(Serialize)]
+pub struct PlayerStats {
+    pub games: usize,
+    pub wins: usize,
+    pub win_rate: f64,
+    pub favorite_comb: Option<String>,
+    pub average_taken: f64,
+}
+
+impl History {
+    pub fn load() -> History {
+        let history = History {
+            games: journal::load(Path::new(HISTORY_FILE)),
+        };
+        info!("HISTORY {} games loaded", history.games.len());
+        history
+    }
+
+    /// Stores a finished game and returns its replay ID.
+    pub fn record(&mut self, result: GameResult) -> usize {
+        if let Err(e) = journal::append(Path::new(HISTORY_FILE), &result) {
+            warn!("HISTORY can't save {}: {}", HISTORY_FILE, e);
+        }
+        self.games.push(result);
+        self.games.len()
+    }
+
+    pub fn game(&self, id: usize) -> Option<&GameResult> {
+        self.games.get(id.checked_sub(1)?)
+    }
+
+    /// Returns the games of a player with their replay IDs, newest first.
+    pub fn player_games(&self, pid: PID) -> Vec<(usize, &GameResult)> {
+        self.games
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, game)| game.players.contains(&pid))
+            .map(|(i, game)| (i + 1, game))
+            .collect()
+    }
+
+    pub fn player_stats(&self, pid: PID) -> PlayerStats {
+        let games = self.player_games(pid);
+        let wins = games
+            .iter()
+            .filter(|(_, game)| game.winner == Some(pid))
+            .count();
+
+        let mut combs = HashMap::new();
+        let (mut takes, mut taken) = (0, 0);
+        for step in games
+            .iter()
+            .flat_map(|(_, game)| game.steps.iter())
+            .filter(|step| step.pid == pid)
+        {
+            if let Some(comb) = &step.comb {
+                *combs.entry(comb.as_str()).or_insert(0) += 1;
+            }
+            if let Step::GetComb = step.step {
+                takes += 1;
+                taken += step.taken;
+            }
+        }
+
+        PlayerStats {
+            games: games.len(),
+            wins,
+            win_rate: if games.is_empty() {
+                0.0
+            } else {
+                wins as f64 / games.len() as f64
+            },
+            favorite_comb: combs
+                .into_iter()
+                .max_by_key(|&(comb, count)| (count, comb))
+                .map(|(comb, _)| comb.to_string()),
+            average_taken: if takes == 0 {
+                0.0
+            } else {
+                taken as f64 / takes as f64
+            },
+        }
+    }
+}
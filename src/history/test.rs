@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashSet;
+
+    use crate::game::*;
+
+    use crate::history::*;
+
+    fn logged(pid: PID, step: Step, comb: Option<&str>, taken: usize) -> LoggedStep {
+        LoggedStep {
+            pid,
+            step,
+            comb: comb.map(|comb| comb.to_string()),
+            taken,
+        }
+    }
+
+    #[test]
+    fn history_test_player_stats() {
+        let mut history = History::default();
+        history.games.push(GameResult {
+            players: vec![1, 2],
+            winner: Some(1),
+            steps: vec![
+                logged(1, Step::GiveComb(HashSet::new()), Some("Pair"), 0),
+                logged(2, Step::GetComb, None, 2),
+                logged(1, Step::GiveComb(HashSet::new()), Some("Pair"), 0),
+                logged(2, Step::TransComb(HashSet::new()), Some("Set"), 0),
+                logged(1, Step::GetComb, None, 3),
+            ],
+            ..GameResult::default()
+        });
+        history.games.push(GameResult {
+            players: vec![2, 3],
+            winner: Some(3),
+            ..GameResult::default()
+        });
+
+        let stats = history.player_stats(1);
+        assert_eq!(stats.games, 1);
+        assert_eq!(stats.wins, 1);
+        assert_eq!(stats.favorite_comb, Some("Pair".to_string()));
+        assert!((stats.average_taken - 3.0).abs() < 1e-9);
+
+        let stats = history.player_stats(2);
+        assert_eq!(stats.games, 2);
+        assert!((stats.win_rate - 0.0).abs() < 1e-9);
+        assert_eq!(stats.favorite_comb, Some("Set".to_string()));
+
+        assert_eq!(history.player_games(2)[0].0, 2);
+        assert!(history.game(0).is_none());
+        assert_eq!(history.game(2).unwrap().winner, Some(3));
+    }
+}
@@ -0,0 +1,143 @@
+//! Keys of the texts are dot separated paths, templates get them as the nested
+//! `t` object, e.g. `{{ t.game.get_card }}`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::account::AccountError;
+use crate::game::StepError;
+use crate::tournament::TournamentError;
+
+mod en;
+mod ru;
+mod test;
+
+pub const LANGUAGES: [Language; 2] = [Language::Ru, Language::En];
+
+/// The language used when the client's one isn't supported.
+pub const DEFAULT_LANGUAGE: Language = Language::Ru;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Language {
+    Ru,
+    En,
+}
+
+impl Language {
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::Ru => "ru",
+            Language::En => "en",
+        }
+    }
+
+    fn catalog(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Language::Ru => ru::CATALOG,
+            Language::En => en::CATALOG,
+        }
+    }
+
+    /// Returns the text from the language's catalog falling back to the
+    /// default language, `None` if the key is unknown.
+    pub fn text(self, key: &str) -> Option<&'static str> {
+        let find = |language: Language| {
+            language
+                .catalog()
+                .iter()
+                .find(|&&(k, _)| k == key)
+                .map(|&(_, text)| text)
+        };
+        find(self).or_else(|| find(DEFAULT_LANGUAGE))
+    }
+
+    /// All texts of the language as nested objects split by dots in keys.
+    pub fn texts(self) -> serde_json::Value {
+        let mut texts = serde_json::Map::new();
+
+        for &(key, text) in DEFAULT_LANGUAGE.catalog().iter().chain(self.catalog()) {
+            let mut path = key.split('.').collect::<Vec<_>>();
+            let name = path.pop().unwrap();
+            let mut node = &mut texts;
+            for part in path {
+                node = match node
+                    .entry(part)
+                    .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()))
+                {
+                    serde_json::Value::Object(map) => map,
+                    _ => panic!("I18N key {} overlaps a text", key),
+                };
+            }
+            node.insert(
+                name.to_string(),
+                serde_json::Value::String(text.to_string()),
+            );
+        }
+
+        serde_json::Value::Object(texts)
+    }
+
+    pub fn from_code(code: &str) -> Option<Language> {
+        match code.split('-').next()?.trim().to_lowercase().as_str() {
+            "ru" => Some(Language::Ru),
+            "en" => Some(Language::En),
+            _ => None,
+        }
+    }
+
+    /// Picks the supported language with the highest quality from an
+    /// `Accept-Language` header, for example `en-US,en;q=0.9,ru;q=0.8`.
+    pub fn from_accept_language(header: &str) -> Language {
+        let mut best: Option<(Language, f64)> = None;
+
+        for range in header.split(',') {
+            let mut parts = range.split(';');
+            let language = match parts.next().and_then(Language::from_code) {
+                Some(language) => language,
+                None => continue,
+            };
+            let quality = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f64>().ok())
+                .unwrap_or(0.0);
+            match best {
+                Some((_, best_quality)) if best_quality >= quality => (),
+                _ if quality > 0.0 => best = Some((language, quality)),
+                _ => (),
+            }
+        }
+
+        best.map_or(DEFAULT_LANGUAGE, |(language, _)| language)
+    }
+}
+
+pub trait Localize {
+    fn localize(&self, language: Language) -> String;
+}
+
+fn localize_key(key: &str, language: Language) -> String {
+    match language.text(key) {
+        Some(text) => text.to_string(),
+        None => {
+            warn!("I18N no text for {}", key);
+            key.to_string()
+        }
+    }
+}
+
+impl Localize for StepError {
+    fn localize(&self, language: Language) -> String {
+        localize_key(&format!("step_error.{:?}", self), language)
+    }
+}
+
+impl Localize for AccountError {
+    fn localize(&self, language: Language) -> String {
+        localize_key(&format!("account_error.{:?}", self), language)
+    }
+}
+
+impl Localize for TournamentError {
+    fn localize(&self, language: Language) -> String {
+        localize_key(&format!("tournament_error.{:?}", self), language)
+    }
+}
@@ -0,0 +1,268 @@
+pub const CATALOG: &[(&str, &str)] = &[
+    ("common.title", "Poker durak"),
+    ("common.back_home", "Back to the <a href=\"/\">main page</a>"),
+    ("common.languages", "Language: <a href=\"/lang/ru\">Русский</a> | <a href=\"/lang/en\">English</a>"),
+    ("common.redirect", "You will be redirected to the main page in 5 seconds.<br />\nIf it doesn't happen, click <a href=\"/\">here</a>."),
+    ("common.yes", "Yes"),
+    ("common.no", "No"),
+    ("common.minutes", "min"),
+    ("common.seconds", "s"),
+    ("index.title", "about the game"),
+    ("index.header", "Poker durak game"),
+    ("index.you_can", "Here you can:"),
+    ("index.play", "Play poker durak"),
+    ("index.login", "Log in or sign up"),
+    ("index.about", "Learn how to play"),
+    ("index.stat", "See the site statistics"),
+    ("index.leaderboard", "See the leaderboard"),
+    ("index.games", "Watch running games"),
+    ("index.rooms", "Play with a time control"),
+    ("index.tournaments", "Tournaments"),
+    ("index.bots", "Bots"),
+    ("index.arena", "Bot arena"),
+    ("index.not_found", "Go to a page which doesn't exist"),
+    ("about.title", "about the game"),
+    ("about.header", "How to play"),
+    ("about.start", "At the <b>start of the game</b> every player is dealt {hand_size} cards"),
+    ("about.states", "The game has two states:"),
+    ("about.passive", "<b>Passive</b>, when no combination is played against the player, and they can take a card (if the deck isn't empty) or play a poker combination against the opponent."),
+    ("about.active", "<b>Active</b>, when a combination is played against the player, and they can either beat it with a stronger combination or take it."),
+    ("about.steps", "The game has the following steps:"),
+    ("about.get_card", "<b>Take a card</b> - you take one card from the deck, the turn passes to the next player."),
+    ("about.give_comb", "<b>Play a combination</b> - you play a poker combination from your cards against your opponent."),
+    ("about.trans_comb", "<b>Pass a combination</b> - you make a poker combination stronger than the one played against you from your cards and the cards on the board, and pass it to your opponent. <i>You must play at least one card from your hand.</i>"),
+    ("about.get_comb", "<b>Take a combination</b> - you take your opponent's combination into your hand, then the opponent's hand and then yours are filled up to {hand_size} cards from the deck, and the opponent gets one more card."),
+    ("about.goal", "<b>The goal of the game</b> is to run out of cards in the hand and in the deck before the opponent"),
+    ("stat.title", "Statistics"),
+    ("stat.header", "Poker durak statistics"),
+    ("stat.all_games", "Games started"),
+    ("stat.now_games", "Games running"),
+    ("winner.header", "You won!"),
+    ("loser.header", "You lost!"),
+    ("series.score", "Series score"),
+    ("series.games", "games played"),
+    ("series.rematch", "Rematch"),
+    ("series.closed", "The opponent is playing another game."),
+    ("tournaments.title", "Tournaments"),
+    ("tournaments.header", "Poker durak tournaments"),
+    ("tournaments.no_tournaments", "There are no tournaments yet."),
+    ("tournaments.name", "Name"),
+    ("tournaments.format", "Format"),
+    ("tournaments.room", "Time control"),
+    ("tournaments.default_room", "standard"),
+    ("tournaments.best_of", "Best of"),
+    ("tournaments.players", "Players"),
+    ("tournaments.stage", "Stage"),
+    ("tournaments.create", "Create a tournament"),
+    ("bots.title", "Bots"),
+    ("bots.header", "Your bots"),
+    ("bots.about", "Bots are programs playing poker durak like you do. Register a bot and give its token to the program."),
+    ("bots.no_bots", "You have no bots yet."),
+    ("bots.token", "The token of"),
+    ("bots.token_once", "Save it now, it isn't shown again. If it is lost, get a new one."),
+    ("bots.reset_token", "New token"),
+    ("bots.create", "Register a bot"),
+    ("bots.connect", "Connecting"),
+    ("bots.connect_with", "A bot connects to the websocket with its token, or with ?token=TOKEN in the address:"),
+    ("bots.protocol", "The messages are described in the <a href=\"/api/protocol/schema\">protocol schema</a>. On its turn a bot gets every step it can make, a bot sending too many messages gets RateLimited. With ?arena in the address a bot plays other bots in the <a href=\"/arena\">arena</a>."),
+    ("format.round_robin", "Round-robin"),
+    ("format.single_elimination", "Single elimination"),
+    ("stage.registration", "Registration"),
+    ("stage.running", "Running"),
+    ("stage.finished", "Finished"),
+    ("tournament.organizer", "Organizer"),
+    ("tournament.join", "Join"),
+    ("tournament.start", "Close the registration and start"),
+    ("tournament.play", "Play your match"),
+    ("tournament.next_match", "You have a match in the tournament"),
+    ("tournament.standings", "Standings"),
+    ("tournament.player", "Player"),
+    ("tournament.matches", "Matches"),
+    ("tournament.match_wins", "Matches won"),
+    ("tournament.games", "Games won and lost"),
+    ("tournament.round", "Round"),
+    ("tournament.bye", "a bye"),
+    ("tournament.winner", "winner"),
+    ("tournament.playing", "playing now"),
+    ("tournament.all", "All tournaments"),
+    ("not_found.header", "Page not found"),
+    ("not_found.text", "Alas, the site's author hasn't come up with this page yet"),
+    ("not_found.links", "You can go to the <a href=\"/\">main page</a> or to the <a href=\"/about\">rules</a>"),
+    ("login.title", "log in"),
+    ("login.header", "Log in to the game"),
+    ("login.login", "Log in"),
+    ("login.register", "Sign up"),
+    ("login.nickname", "Nickname"),
+    ("login.password", "Password"),
+    ("account_error.InvalidNickname", "A nickname must contain 3 to 20 letters, digits, _ or -"),
+    ("account_error.InvalidPassword", "A password must contain at least 6 characters"),
+    ("account_error.NicknameTaken", "This nickname is already taken"),
+    ("account_error.WrongCredentials", "Wrong nickname or password"),
+    ("account_error.NotYourBot", "You have no bot with this nickname"),
+    ("tournament_error.InvalidName", "The name must be from 1 to 64 characters long"),
+    ("tournament_error.InvalidFormat", "Unknown tournament format"),
+    ("tournament_error.InvalidRoom", "Unknown time control"),
+    ("tournament_error.InvalidBestOf", "The number of games must be odd, from 1 to 9"),
+    ("tournament_error.NotFound", "There is no such tournament"),
+    ("tournament_error.NotOrganizer", "Only the organizer can start the tournament"),
+    ("tournament_error.AlreadyStarted", "The tournament has already started"),
+    ("tournament_error.AlreadyJoined", "You have already joined the tournament"),
+    ("tournament_error.NotEnoughPlayers", "At least two players are needed"),
+    ("leaderboard.title", "leaderboard"),
+    ("leaderboard.header", "Leaderboard"),
+    ("leaderboard.player", "Player"),
+    ("leaderboard.rating", "Rating"),
+    ("leaderboard.games", "Games"),
+    ("leaderboard.wins", "Wins"),
+    ("comb.HighestCard", "High card"),
+    ("comb.Pair", "Pair"),
+    ("comb.TwoPairs", "Two pairs"),
+    ("comb.Set", "Three of a kind"),
+    ("comb.Straight", "Straight"),
+    ("comb.Flush", "Flush"),
+    ("comb.FullHouse", "Full house"),
+    ("comb.FourOfAKind", "Four of a kind"),
+    ("comb.StraightFlush", "Straight flush"),
+    ("player.title", "player profile"),
+    ("player.header", "Player"),
+    ("player.rating", "Rating"),
+    ("player.games", "Games played"),
+    ("player.wins", "Wins"),
+    ("player.favorite_comb", "Favorite combination"),
+    ("player.average_taken", "Average cards taken at once"),
+    ("player.played_games", "Played games"),
+    ("player.opponent", "Opponent"),
+    ("player.result", "Result"),
+    ("player.duration", "Duration"),
+    ("player.steps", "Steps"),
+    ("player.draw", "Draw"),
+    ("player.win", "Win"),
+    ("player.loss", "Loss"),
+    ("player.replay", "Replay"),
+    ("player.not_found", "not found"),
+    ("replay.title", "Game replay"),
+    ("replay.players", "Players"),
+    ("replay.winner", "Winner"),
+    ("replay.duration", "Duration"),
+    ("replay.hands", "Initial hands"),
+    ("replay.steps", "Steps"),
+    ("replay.get_card", "takes a card"),
+    ("replay.get_comb", "takes the combination"),
+    ("replay.give_comb", "plays"),
+    ("replay.trans_comb", "passes"),
+    ("games.title", "running games"),
+    ("games.header", "Running games"),
+    ("games.players", "Players"),
+    ("games.duration", "Running, s"),
+    ("games.no_games", "Nobody is playing now"),
+    ("games.watch", "Watch"),
+    ("games.watch_hands", "with players' hands"),
+    ("arena.title", "bot arena"),
+    ("arena.header", "Bot arena"),
+    ("arena.about", "Bots play each other here around the clock. Arena games are rated on their own leaderboard."),
+    ("arena.leaderboard", "Bot leaderboard"),
+    ("arena.matches", "Recent matches"),
+    ("arena.players", "Bots"),
+    ("arena.winner", "Winner"),
+    ("arena.finished", "Finished"),
+    ("arena.replay", "Replay"),
+    ("arena.no_matches", "No matches have been played yet"),
+    ("watch.title", "Watching a game"),
+    ("watch.connecting", "Connecting..."),
+    ("watch.steps", "Steps"),
+    ("watch.players", "Players"),
+    ("watch.chat", "Chat"),
+    ("watch.back", "Back to the <a href=\"/games\">games list</a>"),
+    ("watch.cards", "cards"),
+    ("watch.stepping", "stepping"),
+    ("watch.left", "left the game"),
+    ("watch.finished", "The game is over, the winner"),
+    ("watch.playing", "The game is running"),
+    ("watch.connection_lost", "Connection lost"),
+    ("watch.reveal_delay", "Players' hands are shown with a delay, steps"),
+    ("watch.no_such_game", "The game is not found or has already finished"),
+    ("watch.own_game", "You can't watch a game you or your bot play in"),
+    ("game.title", "game"),
+    ("game.header", "Web interface of the \"poker durak\" game, version 1.0.0"),
+    ("game.about", "About the game"),
+    ("game.not_connected", "Not connected"),
+    ("game.playing_as", "You are playing as"),
+    ("game.logout", "Log out"),
+    ("game.get_card", "Take a card"),
+    ("game.give_comb", "Play a combination"),
+    ("game.get_comb", "Take the combination"),
+    ("game.trans_comb", "Pass the combination"),
+    ("game.surrender", "Surrender"),
+    ("game.waiting", "Waiting for the second player."),
+    ("game.waiting_rematch", "Waiting for the opponent to accept the rematch."),
+    ("game.waiting_tournament", "Waiting for the opponent of your tournament match."),
+    ("game.your_turn", "Your turn"),
+    ("game.your_cards", "Your cards"),
+    ("game.state", "State"),
+    ("game.active", "Active"),
+    ("game.passive", "Passive"),
+    ("game.comb", "Combination"),
+    ("game.board", "Cards on the board"),
+    ("game.deck_size", "Cards in the deck"),
+    ("game.opponent", "Opponent"),
+    ("game.opponent_cards", "Opponent's cards"),
+    ("game.send", "Send"),
+    ("game.you", "You"),
+    ("game.player_left", "left the game"),
+    ("game.protocol_error", "The client version isn't supported by the server, reload the page"),
+    ("game.message_too_long", "The message is too long and wasn't sent"),
+    ("game.already_playing", "You are already playing in another tab"),
+    ("game.connection_lost", "Connection lost"),
+    ("game.home", "To the main page"),
+    ("game.connection_problems", "Connection problems"),
+    ("game.connected", "Connected"),
+    ("game.time_control", "Time control"),
+    ("game.clock", "Your clock"),
+    ("game.out_of_time", "ran out of time"),
+    ("game.server_shutdown", "The server is shutting down, seconds left to finish the game:"),
+    ("game.no_new_games", "The server is shutting down and starts no new games, try again later"),
+    ("time_control.turn", "seconds per step"),
+    ("time_control.total", "seconds per game"),
+    ("time_control.increment", "seconds added after a step"),
+    ("time_control.forfeit", "the player out of time leaves the game"),
+    ("time_control.auto_step", "the server steps for the player out of time"),
+    ("time_control.max_timeouts", "timeouts in a row to leave the game"),
+    ("cli.opponents", "Opponents"),
+    ("cli.error", "Error"),
+    ("cli.left", "has left the game"),
+    ("cli.out_of_time", "ran out of time"),
+    ("cli.not_sent", "The message is too long"),
+    ("cli.server_shutdown", "The server is shutting down, seconds left to finish the game:"),
+    ("cli.your_turn", "your turn"),
+    ("cli.left_to_step", "to step"),
+    ("cli.clock", "clock"),
+    ("cli.cards", "cards"),
+    ("cli.not_playing", "out of the game"),
+    ("cli.deck", "Deck"),
+    ("cli.comb", "Combination to beat"),
+    ("cli.board", "Board"),
+    ("cli.passive", "The board is empty, play a combination or take a card"),
+    ("cli.hand", "Your cards"),
+    ("cli.unknown_command", "Unknown command, type ? for help"),
+    ("cli.no_cards", "Give the numbers of the cards"),
+    ("cli.no_such_card", "No card with the number"),
+    ("cli.empty_message", "The message is empty"),
+    ("cli.help", "Commands:\n  t            take a card\n  p N N ...    play a combination of cards with the numbers\n  x N N ...    pass the combination: your cards and the board's ones\n  g            take the combination\n  m TEXT       send a chat message\n  q            surrender and quit\n  Enter        redraw the table"),
+    ("cli.waiting", "Waiting for the second player..."),
+    ("cli.won", "You won!"),
+    ("cli.lost", "You lost!"),
+    ("cli.password", "Password: "),
+    ("cli.playing_elsewhere", "You are already playing from another connection"),
+    ("cli.you", "You"),
+    ("cli.bot", "Bot"),
+    ("cli.took_card", "takes a card"),
+    ("cli.took_comb", "takes the combination"),
+    ("cli.pass_to", "Pass the terminal to"),
+    ("cli.press_enter", "then press Enter"),
+    ("cli.won_game", "has won the game"),
+    ("step_error.InvalidPID", "You can't make a step now"),
+    ("step_error.InvalidStepType", "You can't make this kind of step now"),
+    ("step_error.InvalidCards", "You don't have cards for this step"),
+    ("step_error.InvalidComb", "Your cards are not a poker combination"),
+    ("step_error.WeakComb", "Your combination is too weak"),
+];
@@ -0,0 +1,268 @@
+pub const CATALOG: &[(&str, &str)] = &[
+    ("common.title", "Покерный дурак"),
+    ("common.back_home", "Вернуться на <a href=\"/\">главную страницу</a>"),
+    ("common.languages", "Язык: <a href=\"/lang/ru\">Русский</a> | <a href=\"/lang/en\">English</a>"),
+    ("common.redirect", "Через 5 секунд вы будете автоматически перенаправлены на главную страницу.<br />\nЕсли этого не произошло, нажмите <a href=\"/\">здесь</a>."),
+    ("common.yes", "Да"),
+    ("common.no", "Нет"),
+    ("common.minutes", "мин"),
+    ("common.seconds", "с"),
+    ("index.title", "об игре"),
+    ("index.header", "Игра покерный дурак"),
+    ("index.you_can", "Здесь вы можете:"),
+    ("index.play", "Поиграть в покерного дурака оффлайн"),
+    ("index.login", "Войти или зарегистрироваться"),
+    ("index.about", "Узнать, как играть"),
+    ("index.stat", "Увидеть статистику сайта"),
+    ("index.leaderboard", "Посмотреть рейтинг игроков"),
+    ("index.games", "Посмотреть текущие игры"),
+    ("index.rooms", "Поиграть с контролем времени"),
+    ("index.tournaments", "Турниры"),
+    ("index.bots", "Боты"),
+    ("index.arena", "Арена ботов"),
+    ("index.not_found", "Перейти на несуществующую страницу"),
+    ("about.title", "об игре"),
+    ("about.header", "Как играть"),
+    ("about.start", "В <b>начале игры</b> каждому игроку раздаётся карт: {hand_size}"),
+    ("about.states", "В игре есть два состояния:"),
+    ("about.passive", "<b>Пассивное</b>, в котором против игрока не выложена комбинация и он может взять карту (если они остались в колоде) или выложить против соперника покерную комбинацию."),
+    ("about.active", "<b>Активное</b>, где против игрока выложена комбинация и он может либо отразить её более сильной комбинацией, или забрать её себе."),
+    ("about.steps", "В игре уже упоминались следующие действия:"),
+    ("about.get_card", "<b>Взять карту</b> - вы берёте одну карту из колоды, действие передаётся следующему игроку."),
+    ("about.give_comb", "<b>Выложить комбинацию</b> - вы выкладываете из своих карт покерную комбинацию против вашего соперника."),
+    ("about.trans_comb", "<b>Перевести комбинацию</b> - вы составляете из своих карт, а также карт на доске покерную комбинацию, более сильную, чем та, что выложена против вас и переводите её своему сопернику. <i>При этом вы должны выложить как минимум одну карту из своей руки.</i>"),
+    ("about.get_comb", "<b>Взять комбинацию</b> - вы забираете себе в руку комбинацию вашего соперника, затем количество карт сначала у противники, потом у вас дополняется картами из колоды до {hand_size} и противник дополнительно получает одну карту."),
+    ("about.goal", "<b>Цель игры</b> - остаться без карт в руках и колоде раньше соперника"),
+    ("stat.title", "Статистика"),
+    ("stat.header", "Статистика игры покерный дурак"),
+    ("stat.all_games", "Начато игр"),
+    ("stat.now_games", "Идёт игр"),
+    ("winner.header", "Вы выиграли!"),
+    ("loser.header", "Вы проиграли!"),
+    ("series.score", "Счёт серии"),
+    ("series.games", "сыграно партий"),
+    ("series.rematch", "Реванш"),
+    ("series.closed", "Соперник играет другую партию."),
+    ("tournaments.title", "Турниры"),
+    ("tournaments.header", "Турниры по покерному дураку"),
+    ("tournaments.no_tournaments", "Турниров пока нет."),
+    ("tournaments.name", "Название"),
+    ("tournaments.format", "Формат"),
+    ("tournaments.room", "Контроль времени"),
+    ("tournaments.default_room", "стандартный"),
+    ("tournaments.best_of", "Партий в матче"),
+    ("tournaments.players", "Игроки"),
+    ("tournaments.stage", "Стадия"),
+    ("tournaments.create", "Создать турнир"),
+    ("bots.title", "Боты"),
+    ("bots.header", "Ваши боты"),
+    ("bots.about", "Боты - это программы, которые играют в покерного дурака так же, как вы. Зарегистрируйте бота и передайте его токен программе."),
+    ("bots.no_bots", "У вас пока нет ботов."),
+    ("bots.token", "Токен бота"),
+    ("bots.token_once", "Сохраните его сейчас, больше он не будет показан. Если он потерян, получите новый."),
+    ("bots.reset_token", "Новый токен"),
+    ("bots.create", "Зарегистрировать бота"),
+    ("bots.connect", "Подключение"),
+    ("bots.connect_with", "Бот подключается к вебсокету со своим токеном или с ?token=ТОКЕН в адресе:"),
+    ("bots.protocol", "Сообщения описаны в <a href=\"/api/protocol/schema\">схеме протокола</a>. В свой ход бот получает все ходы, которые он может сделать, бот, отправляющий слишком много сообщений, получает RateLimited. С ?arena в адресе бот играет с другими ботами на <a href=\"/arena\">арене</a>."),
+    ("format.round_robin", "Круговой"),
+    ("format.single_elimination", "Олимпийская система"),
+    ("stage.registration", "Регистрация"),
+    ("stage.running", "Идёт"),
+    ("stage.finished", "Завершён"),
+    ("tournament.organizer", "Организатор"),
+    ("tournament.join", "Участвовать"),
+    ("tournament.start", "Закрыть регистрацию и начать"),
+    ("tournament.play", "Играть свой матч"),
+    ("tournament.next_match", "У вас есть матч в турнире"),
+    ("tournament.standings", "Турнирная таблица"),
+    ("tournament.player", "Игрок"),
+    ("tournament.matches", "Матчи"),
+    ("tournament.match_wins", "Выиграно матчей"),
+    ("tournament.games", "Партии: выиграно и проиграно"),
+    ("tournament.round", "Тур"),
+    ("tournament.bye", "без соперника"),
+    ("tournament.winner", "победитель"),
+    ("tournament.playing", "идёт игра"),
+    ("tournament.all", "Все турниры"),
+    ("not_found.header", "Страница не найдена"),
+    ("not_found.text", "Увы, но такую страницу автор сайта ещё не придумал"),
+    ("not_found.links", "Вы можете перейти на <a href=\"/\">главную страницу</a> или на <a href=\"/about\">страницу описания</a>"),
+    ("login.title", "вход"),
+    ("login.header", "Вход в игру"),
+    ("login.login", "Войти"),
+    ("login.register", "Зарегистрироваться"),
+    ("login.nickname", "Имя игрока"),
+    ("login.password", "Пароль"),
+    ("account_error.InvalidNickname", "Имя игрока должно содержать от 3 до 20 букв, цифр, символов _ или -"),
+    ("account_error.InvalidPassword", "Пароль должен содержать не менее 6 символов"),
+    ("account_error.NicknameTaken", "Это имя уже занято"),
+    ("account_error.WrongCredentials", "Неверное имя или пароль"),
+    ("account_error.NotYourBot", "У вас нет бота с таким именем"),
+    ("tournament_error.InvalidName", "Название должно быть длиной от 1 до 64 символов"),
+    ("tournament_error.InvalidFormat", "Неизвестный формат турнира"),
+    ("tournament_error.InvalidRoom", "Неизвестный контроль времени"),
+    ("tournament_error.InvalidBestOf", "Число партий должно быть нечётным, от 1 до 9"),
+    ("tournament_error.NotFound", "Такого турнира нет"),
+    ("tournament_error.NotOrganizer", "Начать турнир может только организатор"),
+    ("tournament_error.AlreadyStarted", "Турнир уже начался"),
+    ("tournament_error.AlreadyJoined", "Вы уже участвуете в турнире"),
+    ("tournament_error.NotEnoughPlayers", "Нужно хотя бы два игрока"),
+    ("leaderboard.title", "рейтинг"),
+    ("leaderboard.header", "Рейтинг игроков"),
+    ("leaderboard.player", "Игрок"),
+    ("leaderboard.rating", "Рейтинг"),
+    ("leaderboard.games", "Игр"),
+    ("leaderboard.wins", "Побед"),
+    ("comb.HighestCard", "Старшая карта"),
+    ("comb.Pair", "Пара"),
+    ("comb.TwoPairs", "Две пары"),
+    ("comb.Set", "Сет"),
+    ("comb.Straight", "Стрит"),
+    ("comb.Flush", "Флеш"),
+    ("comb.FullHouse", "Фулл-хаус"),
+    ("comb.FourOfAKind", "Каре"),
+    ("comb.StraightFlush", "Стрит-флеш"),
+    ("player.title", "профиль игрока"),
+    ("player.header", "Игрок"),
+    ("player.rating", "Рейтинг"),
+    ("player.games", "Сыграно игр"),
+    ("player.wins", "Побед"),
+    ("player.favorite_comb", "Любимая комбинация"),
+    ("player.average_taken", "В среднем карт забрано за раз"),
+    ("player.played_games", "Сыгранные игры"),
+    ("player.opponent", "Соперник"),
+    ("player.result", "Результат"),
+    ("player.duration", "Длительность"),
+    ("player.steps", "Ходов"),
+    ("player.draw", "Ничья"),
+    ("player.win", "Победа"),
+    ("player.loss", "Поражение"),
+    ("player.replay", "Запись игры"),
+    ("player.not_found", "не найден"),
+    ("replay.title", "Запись игры"),
+    ("replay.players", "Игроки"),
+    ("replay.winner", "Победитель"),
+    ("replay.duration", "Длительность"),
+    ("replay.hands", "Начальные карты"),
+    ("replay.steps", "Ходы"),
+    ("replay.get_card", "берёт карту"),
+    ("replay.get_comb", "забирает комбинацию"),
+    ("replay.give_comb", "выкладывает"),
+    ("replay.trans_comb", "переводит"),
+    ("games.title", "текущие игры"),
+    ("games.header", "Текущие игры"),
+    ("games.players", "Игроки"),
+    ("games.duration", "Идёт, с"),
+    ("games.no_games", "Сейчас никто не играет"),
+    ("games.watch", "Смотреть"),
+    ("games.watch_hands", "с картами игроков"),
+    ("arena.title", "арена ботов"),
+    ("arena.header", "Арена ботов"),
+    ("arena.about", "Здесь боты круглосуточно играют друг с другом. Игры на арене учитываются в отдельном рейтинге."),
+    ("arena.leaderboard", "Рейтинг ботов"),
+    ("arena.matches", "Последние матчи"),
+    ("arena.players", "Боты"),
+    ("arena.winner", "Победитель"),
+    ("arena.finished", "Закончен"),
+    ("arena.replay", "Запись"),
+    ("arena.no_matches", "Матчей пока не было"),
+    ("watch.title", "Просмотр игры"),
+    ("watch.connecting", "Подключение..."),
+    ("watch.steps", "Ходов"),
+    ("watch.players", "Игроки"),
+    ("watch.chat", "Чат"),
+    ("watch.back", "Вернуться к <a href=\"/games\">списку игр</a>"),
+    ("watch.cards", "карт"),
+    ("watch.stepping", "ходит"),
+    ("watch.left", "вышел из игры"),
+    ("watch.finished", "Игра окончена, победитель"),
+    ("watch.playing", "Идёт игра"),
+    ("watch.connection_lost", "Соединение потеряно"),
+    ("watch.reveal_delay", "Карты игроков показываются с задержкой, ходов"),
+    ("watch.no_such_game", "Игра не найдена или уже закончилась"),
+    ("watch.own_game", "Нельзя смотреть игру, в которой играете вы или ваш бот"),
+    ("game.title", "игра"),
+    ("game.header", "Веб интерфейс игры \"покерный дурак\", версия 1.0.0"),
+    ("game.about", "Об игре"),
+    ("game.not_connected", "Соединение не установлено"),
+    ("game.playing_as", "Вы играете как"),
+    ("game.logout", "Выйти"),
+    ("game.get_card", "Взять карту"),
+    ("game.give_comb", "Выложить комбинацию"),
+    ("game.get_comb", "Взять комбинацию"),
+    ("game.trans_comb", "Перевести комбинацию"),
+    ("game.surrender", "Сдаться"),
+    ("game.waiting", "Ждём второго игрока."),
+    ("game.waiting_rematch", "Ждём, пока соперник примет реванш."),
+    ("game.waiting_tournament", "Ждём соперника по турнирному матчу."),
+    ("game.your_turn", "Ваш ход"),
+    ("game.your_cards", "Ваши карты"),
+    ("game.state", "Состояние"),
+    ("game.active", "Активное"),
+    ("game.passive", "Пассивное"),
+    ("game.comb", "Комбинация"),
+    ("game.board", "Карты на доске"),
+    ("game.deck_size", "Карт в колоде"),
+    ("game.opponent", "Противник"),
+    ("game.opponent_cards", "Карт у противника"),
+    ("game.send", "Отправить"),
+    ("game.you", "Вы"),
+    ("game.player_left", "покинул игру"),
+    ("game.protocol_error", "Версия клиента не поддерживается сервером, обновите страницу"),
+    ("game.message_too_long", "Слишком длинное сообщение, не удалось отправить"),
+    ("game.already_playing", "Вы уже играете в другой вкладке"),
+    ("game.connection_lost", "Обрыв соединения"),
+    ("game.home", "На главную страницу"),
+    ("game.connection_problems", "Проблемы со связью"),
+    ("game.connected", "Соединение установлено"),
+    ("game.time_control", "Контроль времени"),
+    ("game.clock", "Ваши часы"),
+    ("game.out_of_time", "не успел сделать ход"),
+    ("game.server_shutdown", "Сервер выключается, секунд осталось, чтобы доиграть:"),
+    ("game.no_new_games", "Сервер выключается и не начинает новых игр, попробуйте позже"),
+    ("time_control.turn", "секунд на ход"),
+    ("time_control.total", "секунд на партию"),
+    ("time_control.increment", "секунд добавляется после хода"),
+    ("time_control.forfeit", "не успевший игрок покидает игру"),
+    ("time_control.auto_step", "за не успевшего игрока ходит сервер"),
+    ("time_control.max_timeouts", "пропусков хода подряд до выхода из игры"),
+    ("cli.opponents", "Соперники"),
+    ("cli.error", "Ошибка"),
+    ("cli.left", "вышел из игры"),
+    ("cli.out_of_time", "не успел сходить"),
+    ("cli.not_sent", "Сообщение слишком длинное"),
+    ("cli.server_shutdown", "Сервер выключается, секунд осталось, чтобы доиграть:"),
+    ("cli.your_turn", "ваш ход"),
+    ("cli.left_to_step", "на ход"),
+    ("cli.clock", "часы"),
+    ("cli.cards", "карт"),
+    ("cli.not_playing", "вне игры"),
+    ("cli.deck", "Колода"),
+    ("cli.comb", "Побить комбинацию"),
+    ("cli.board", "На столе"),
+    ("cli.passive", "Стол пуст, выложите комбинацию или возьмите карту"),
+    ("cli.hand", "Ваши карты"),
+    ("cli.unknown_command", "Неизвестная команда, введите ? для справки"),
+    ("cli.no_cards", "Укажите номера карт"),
+    ("cli.no_such_card", "Нет карты с номером"),
+    ("cli.empty_message", "Пустое сообщение"),
+    ("cli.help", "Команды:\n  t            взять карту\n  p N N ...    выложить комбинацию из карт с номерами\n  x N N ...    перевести комбинацию: ваши карты и карты со стола\n  g            взять комбинацию\n  m ТЕКСТ      написать в чат\n  q            сдаться и выйти\n  Enter        перерисовать стол"),
+    ("cli.waiting", "Ждём второго игрока..."),
+    ("cli.won", "Вы выиграли!"),
+    ("cli.lost", "Вы проиграли!"),
+    ("cli.password", "Пароль: "),
+    ("cli.playing_elsewhere", "Вы уже играете с другого подключения"),
+    ("cli.you", "Вы"),
+    ("cli.bot", "Бот"),
+    ("cli.took_card", "берёт карту"),
+    ("cli.took_comb", "берёт комбинацию"),
+    ("cli.pass_to", "Передайте терминал игроку"),
+    ("cli.press_enter", "затем нажмите Enter"),
+    ("cli.won_game", "выиграл партию"),
+    ("step_error.InvalidPID", "Вы не можете совершить шаг сейчас"),
+    ("step_error.InvalidStepType", "Вы не имеете права делать данный тип шага"),
+    ("step_error.InvalidCards", "У вас нет карт, чтобы сделать этот шаг"),
+    ("step_error.InvalidComb", "Ваши карты не являются покерной комбинацией"),
+    ("step_error.WeakComb", "Ваша комбинация слишком слаба"),
+];
@@ -0,0 +1,37 @@
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashSet;
+
+    use crate::i18n::*;
+
+    #[test]
+    fn i18n_test_accept_language() {
+        assert_eq!(Language::from_accept_language(""), Language::Ru);
+        assert_eq!(
+            Language::from_accept_language("en-US,en;q=0.9"),
+            Language::En
+        );
+        assert_eq!(
+            Language::from_accept_language("de-DE,de;q=0.9,ru;q=0.5,en;q=0.8"),
+            Language::En
+        );
+        assert_eq!(Language::from_accept_language("en;q=0,ru"), Language::Ru);
+        assert_eq!(Language::from_accept_language("fr, *;q=0.5"), Language::Ru);
+    }
+
+    #[test]
+    fn i18n_test_catalogs() {
+        let keys = |catalog: &'static [(&'static str, &'static str)]| {
+            catalog.iter().map(|&(key, _)| key).collect::<Vec<_>>()
+        };
+        let (ru, en) = (keys(ru::CATALOG), keys(en::CATALOG));
+        assert_eq!(ru.iter().collect::<HashSet<_>>().len(), ru.len());
+        assert_eq!(ru, en);
+        assert_eq!(
+            StepError::WeakComb.localize(Language::En),
+            "Your combination is too weak"
+        );
+        assert_eq!(Language::En.texts()["game"]["get_card"], "Take a card");
+    }
+}
@@ -0,0 +1,77 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+mod test;
+
+fn line<T: Serialize>(record: &T) -> io::Result<Vec<u8>> {
+    let mut line = serde_json::to_vec(record)?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+/// Lines torn by a crash are dropped and the file is rewritten without them.
+pub fn load<T: Serialize + DeserializeOwned>(path: &Path) -> Vec<T> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut records = Vec::new();
+    let mut dropped = 0;
+    let mut reader = BufReader::new(file);
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) => break,
+            Ok(_) if buf.last() != Some(&b'\n') => dropped += 1,
+            Ok(_) => match serde_json::from_slice(&buf) {
+                Ok(record) => records.push(record),
+                Err(_) => dropped += 1,
+            },
+            Err(e) => {
+                warn!("JOURNAL can't read {}: {}", path.display(), e);
+                dropped += 1;
+                break;
+            }
+        }
+    }
+
+    if dropped > 0 {
+        warn!("JOURNAL {} lines of {} dropped", dropped, path.display());
+        if let Err(e) = rewrite(path, &records) {
+            warn!("JOURNAL can't rewrite {}: {}", path.display(), e);
+        }
+    }
+    records
+}
+
+pub fn append<T: Serialize>(path: &Path, record: &T) -> io::Result<()> {
+    let line = line(record)?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(&line)
+}
+
+/// A crash leaves either the old or the new file whole.
+fn rewrite<T: Serialize>(path: &Path, records: &[T]) -> io::Result<()> {
+    let temp = temp_path(path);
+    let mut file = io::BufWriter::new(File::create(&temp)?);
+    for record in records {
+        file.write_all(&line(record)?)?;
+    }
+    file.into_inner()?.sync_all()?;
+    fs::rename(&temp, path)
+}
@@ -0,0 +1,35 @@
+#[cfg(test)]
+mod tests {
+
+    use std::fs;
+    use std::io::Write;
+
+    use crate::journal::*;
+
+    #[test]
+    fn journal_test_append_and_load() {
+        let path = std::env::temp_dir().join(format!("poker-durak-{}.jsonl", std::process::id()));
+        fs::remove_file(&path).ok();
+        assert!(load::<u32>(&path).is_empty());
+
+        append(&path, &1).unwrap();
+        append(&path, &2).unwrap();
+        assert_eq!(load::<u32>(&path), vec![1, 2]);
+
+        // A line torn by a crash is dropped and doesn't spoil the next record
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"3")
+            .unwrap();
+        assert_eq!(load::<u32>(&path), vec![1, 2]);
+        append(&path, &4).unwrap();
+        assert_eq!(load::<u32>(&path), vec![1, 2, 4]);
+
+        rewrite(&path, &[5, 6]).unwrap();
+        assert_eq!(load::<u32>(&path), vec![5, 6]);
+        assert!(!temp_path(&path).exists());
+        fs::remove_file(&path).unwrap();
+    }
+}
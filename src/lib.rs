@@ -0,0 +1,25 @@
+//! Poker durak: the game, the server's building blocks and the protocol shared
+//! by the server (`main.rs`) and the terminal client (`bin/poker-durak-cli.rs`).
+
+#[macro_use]
+extern crate log;
+
+pub mod account;
+pub mod arena;
+pub mod assets;
+pub mod bot;
+pub mod card;
+pub mod client;
+pub mod comb;
+pub mod config;
+pub mod game;
+pub mod history;
+pub mod i18n;
+pub mod journal;
+pub mod protocol;
+pub mod rate_limit;
+pub mod rating;
+pub mod series;
+pub mod templates;
+pub mod tournament;
+pub mod web;
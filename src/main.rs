@@ -1,102 +1,455 @@
 use std::collections::{HashMap, HashSet};
-use std::env::args;
-use std::fs::File;
-use std::io::prelude::*;
-use std::sync::{mpsc, Arc, Mutex};
-use std::thread;
-use std::thread::sleep;
+use std::env::{args, var};
+use std::net::ToSocketAddrs;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
-use rand::{thread_rng, Rng};
-
-use serde::{Deserialize, Serialize};
-
-#[macro_use]
-extern crate rouille;
+use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
+use tera::Context;
+use tokio::sync::{mpsc, watch, Notify};
+use tokio::time::{sleep, sleep_until, timeout_at};
+use warp::ws::{Message, WebSocket};
 
 #[macro_use]
 extern crate log;
 
-use rouille::content_encoding::apply;
-use rouille::input;
-use rouille::websocket;
-use rouille::Response;
+use poker_durak::{try_or_400, try_or_404};
+
+use poker_durak::account::*;
+use poker_durak::arena::*;
+use poker_durak::assets::*;
+use poker_durak::card::*;
+use poker_durak::config::*;
+use poker_durak::game::*;
+use poker_durak::history::*;
+use poker_durak::i18n::*;
+use poker_durak::protocol::*;
+use poker_durak::rate_limit::*;
+use poker_durak::rating::*;
+use poker_durak::series::*;
+use poker_durak::templates::*;
+use poker_durak::tournament::*;
+use poker_durak::web::*;
+
+mod test;
 
-mod card;
-mod comb;
-mod game;
+const PLAYING_ACTIVITY_WAIT: Duration = Duration::from_millis(200);
+const RATING_WINDOW: f64 = 100.0;
+const RATING_WINDOW_GROWTH: f64 = 10.0;
+const SPECTATOR_REVEAL_DELAY: usize = 6;
+const ARENA_MATCHES: usize = 50;
+
+struct RunningGame {
+    players: Vec<usize>,
+    started: Instant,
+    requests: mpsc::UnboundedSender<(PID, GameRequest)>,
+}
 
-use crate::card::*;
-use crate::game::*;
+/// `room: None` is the default room.
+#[derive(Clone, PartialEq)]
+struct GameQueue {
+    room: Option<String>,
+    rematch: Option<usize>,
+    tournament: Option<usize>,
+    arena: bool,
+}
 
-const HEARTBIT_INTERVAL: Duration = Duration::from_secs(15);
-const TIMEOUT: Duration = Duration::from_secs(300);
-const PLAYING_ACTIVITY_WAIT: Duration = Duration::from_millis(200);
-const WS_CLOSED_WAIT: Duration = Duration::from_secs(5);
-const WS_UPDATE: Duration = Duration::from_millis(100);
-const REFRESH_DURATION: Duration = Duration::from_millis(250);
-const MAX_MESSAGE_LENGTH: usize = 4096;
+/// A player's connection to a running game.
+struct PlayerGame {
+    gid: usize,
+    client: GameChannelClient,
+}
+
+/// A player waiting for an opponent in the same queue.
+struct WaitingPlayer {
+    since: Instant,
+    queue: GameQueue,
+    /// Wakes the player to look for an opponent again or to join a game.
+    wake: Arc<Notify>,
+}
 
 struct GamePool {
     players: HashSet<usize>,
-    players_channels: HashMap<usize, GameChannelClient>,
-    players_time: HashMap<usize, Option<Instant>>,
-    waiting_players: HashSet<usize>,
-    on_delete: HashMap<usize, Option<GameChannelClient>>,
+    players_channels: HashMap<usize, PlayerGame>,
+    waiting_players: HashMap<usize, WaitingPlayer>,
+    on_delete: HashMap<usize, Option<PlayerGame>>,
+    games: HashMap<usize, RunningGame>,
+    ratings: Arc<Mutex<Ratings>>,
+    history: Arc<Mutex<History>>,
+    arena: Arc<Mutex<Arena>>,
+    series: SeriesPool,
+    tournaments: Tournaments,
+    config: Arc<Config>,
     counter: usize,
     playing: usize,
+    /// When running games are stopped, set once the server is shutting down.
+    shutdown: watch::Sender<Option<Instant>>,
+    /// Every game holds a clone until it is recorded, dropped on shutdown.
+    drain: Option<mpsc::Sender<()>>,
+}
+
+#[derive(Serialize)]
+struct LeaderboardEntry {
+    nickname: String,
+    rating: i64,
+    games: usize,
+    wins: usize,
+}
+
+#[derive(Serialize)]
+struct ArenaMatchEntry {
+    replay: usize,
+    players: Vec<String>,
+    winner: Option<String>,
+    finished: u64,
+}
+
+#[derive(Serialize)]
+struct ArenaView {
+    leaderboard: Vec<LeaderboardEntry>,
+    matches: Vec<ArenaMatchEntry>,
+}
+
+#[derive(Serialize)]
+struct RoomEntry<'a> {
+    name: &'a str,
+    time_control: &'a TimeControl,
+}
+
+#[derive(Serialize)]
+struct TournamentEntry<'a> {
+    id: usize,
+    name: &'a str,
+    format: Format,
+    room: &'a Option<String>,
+    best_of: usize,
+    stage: Stage,
+    players: usize,
+}
+
+#[derive(Serialize)]
+struct MatchEntry<'a> {
+    players: Vec<String>,
+    wins: &'a [usize],
+    games: usize,
+    winner: Option<String>,
+    playing: bool,
+}
+
+#[derive(Serialize)]
+struct StandingEntry {
+    nickname: String,
+    matches: usize,
+    match_wins: usize,
+    game_wins: usize,
+    game_losses: usize,
+}
+
+/// A tournament as seen by a player, `None` is a guest.
+#[derive(Serialize)]
+struct TournamentView<'a> {
+    id: usize,
+    name: &'a str,
+    organizer: String,
+    format: Format,
+    room: &'a Option<String>,
+    best_of: usize,
+    stage: Stage,
+    players: Vec<String>,
+    rounds: Vec<Vec<MatchEntry<'a>>>,
+    standings: Vec<StandingEntry>,
+    winner: Option<String>,
+    joined: bool,
+    organizing: bool,
+    has_match: bool,
+}
+
+/// A bot's API token, shown once after it was made.
+#[derive(Serialize)]
+struct BotToken {
+    nickname: String,
+    token: String,
+}
+
+#[derive(Serialize)]
+struct SeriesScore {
+    nickname: String,
+    wins: usize,
+}
+
+/// The series of the player shown after a game, the player's score goes first.
+#[derive(Serialize)]
+struct SeriesEntry {
+    id: usize,
+    games: usize,
+    score: Vec<SeriesScore>,
+    open: bool,
+}
+
+#[derive(Serialize)]
+struct RunningGameEntry {
+    id: usize,
+    players: Vec<String>,
+    duration: u64,
+}
+
+#[derive(Serialize)]
+struct PlayerGameEntry {
+    id: usize,
+    opponents: Vec<String>,
+    won: Option<bool>,
+    duration: u64,
+    steps: usize,
+}
+
+#[derive(Serialize)]
+struct PlayerProfile {
+    nickname: String,
+    rating: i64,
+    stats: PlayerStats,
+    games: Vec<PlayerGameEntry>,
+}
+
+#[derive(Serialize)]
+struct Replay<'a> {
+    players: Vec<String>,
+    winner: Option<String>,
+    duration: u64,
+    hands: Vec<(String, &'a HashSet<Card>)>,
+    steps: Vec<(String, &'a LoggedStep)>,
+}
+
+fn get_sid(request: &Request) -> Option<String> {
+    request.cookie("sid").map(|sid| sid.to_string())
+}
+
+fn get_pid(request: &Request, accounts: &Mutex<Accounts>) -> Option<usize> {
+    get_sid(request).and_then(|sid| accounts.lock().unwrap().session_pid(&sid))
 }
 
-fn get_sid(request: &rouille::Request) -> Option<usize> {
-    if let Some((_, val)) = input::cookies(&request).find(|&(n, _)| n == "sid") {
-        match val.trim().parse::<usize>() {
-            Ok(sid) => Some(sid),
-            _ => None,
+/// The token is taken from the `Authorization` header or the `token` parameter.
+fn get_bot_pid(request: &Request, accounts: &Mutex<Accounts>) -> Option<usize> {
+    let token = request
+        .header("Authorization")
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string())
+        .or_else(|| request.get_param("token"))?;
+    accounts.lock().unwrap().token_pid(&token)
+}
+
+/// Whether the client asks for the websocket subprotocol.
+fn asks_protocol(request: &Request, protocol: &str) -> bool {
+    request
+        .header("Sec-WebSocket-Protocol")
+        .is_some_and(|header| header.split(',').any(|p| p.trim() == protocol))
+}
+
+/// The language chosen on the site, otherwise the browser's one.
+fn get_language(request: &Request) -> Language {
+    request
+        .cookie("lang")
+        .and_then(Language::from_code)
+        .unwrap_or_else(|| {
+            Language::from_accept_language(request.header("Accept-Language").unwrap_or(""))
+        })
+}
+
+fn form_field(fields: &[(String, String)], name: &str) -> String {
+    match fields.iter().find(|(n, _)| n == name) {
+        Some((_, val)) => val.clone(),
+        None => String::new(),
+    }
+}
+
+fn session_response(request: &Request, result: Result<String, AccountError>) -> Response {
+    // TLS is terminated by a proxy in front of the server
+    let secure = match request.header("X-Forwarded-Proto") {
+        Some(proto) if proto.eq_ignore_ascii_case("https") => "; Secure",
+        _ => "",
+    };
+    match result {
+        Ok(sid) => Response::redirect_303("/game").with_additional_header(
+            "Set-Cookie",
+            format!(
+                "sid={}; Path=/; Max-Age={}; HttpOnly; SameSite=Strict{}",
+                sid, SESSION_LIFETIME, secure
+            ),
+        ),
+        Err(e) => {
+            info!("ACCOUNT error {}", e);
+            Response::redirect_303(format!("/login?error={:?}", e))
         }
-    } else {
-        None
     }
 }
 
-fn data_by_url(url: &str) -> &'static str {
-    match url {
-        "/favicon.ico" => "image/png",
-        url if url.ends_with(".css") => "text/css; charset=UTF-8",
-        url if url.ends_with(".html") => "text/html; charset=UTF-8",
-        url if url.ends_with(".js") => "text/javascript; charset=UTF-8",
-        url if url.ends_with(".ttf") => "font/ttf",
-        _ => "text/plain; charset=UTF-8",
+fn tournament_response(id: usize, result: Result<(), TournamentError>) -> Response {
+    match result {
+        Ok(()) => Response::redirect_303(format!("/tournament/{}", id)),
+        Err(e) => {
+            info!("TOURNAMENT {} error {}", id, e);
+            Response::redirect_303(format!("/tournament/{}?error={:?}", id, e))
+        }
     }
 }
 
-fn router(url: &str) -> &str {
-    match url {
-        "/" => "/index.html",
-        "/stat" => "/stat.html",
-        "/about" => "/about.html",
-        "/winner" => "/winner.html",
-        "/loser" => "/loser.html",
-        "/game" => "/game.html",
-        url => url,
+fn bots_context(accounts: &Accounts, pid: usize, host: &str, token: Option<BotToken>) -> Context {
+    let mut context = Context::new();
+    context.insert("bots", &accounts.bots(pid));
+    context.insert("host", host);
+    context.insert("token", &token);
+    context
+}
+
+fn tournament_view<'a>(
+    id: usize,
+    tournament: &'a Tournament,
+    accounts: &Accounts,
+    pid: Option<usize>,
+) -> TournamentView<'a> {
+    TournamentView {
+        id,
+        name: &tournament.name,
+        organizer: accounts.nickname(tournament.organizer),
+        format: tournament.format,
+        room: &tournament.room,
+        best_of: tournament.best_of,
+        stage: tournament.stage,
+        players: tournament
+            .players
+            .iter()
+            .map(|&pid| accounts.nickname(pid))
+            .collect(),
+        rounds: tournament
+            .rounds
+            .iter()
+            .map(|round| {
+                round
+                    .iter()
+                    .map(|m| MatchEntry {
+                        players: m
+                            .players
+                            .iter()
+                            .map(|&pid| accounts.nickname(pid))
+                            .collect(),
+                        wins: &m.wins,
+                        games: m.games,
+                        winner: m.winner.map(|pid| accounts.nickname(pid)),
+                        playing: m.is_playing(),
+                    })
+                    .collect()
+            })
+            .collect(),
+        standings: tournament
+            .standings()
+            .into_iter()
+            .map(|standing| StandingEntry {
+                nickname: accounts.nickname(standing.pid),
+                matches: standing.matches,
+                match_wins: standing.match_wins,
+                game_wins: standing.game_wins,
+                game_losses: standing.game_losses,
+            })
+            .collect(),
+        winner: tournament.winner().map(|pid| accounts.nickname(pid)),
+        joined: pid.is_some_and(|pid| tournament.players.contains(&pid)),
+        organizing: pid == Some(tournament.organizer),
+        has_match: pid.is_some_and(|pid| tournament.has_match(pid)),
     }
 }
 
-fn set_cookies(request: &rouille::Request, resp: Response) -> Response {
-    match get_sid(request) {
-        Some(sid) => {
-            info!("SID {}", sid);
-            resp
-        }
-        None => {
-            let sid = thread_rng().gen::<usize>();
-            info!("SID NEW {}", sid);
-            resp.with_additional_header("Set-Cookie", format!("sid={}; HttpOnly", sid))
-        }
+/// `None` if there is no such game for the player, only bots play in the arena.
+fn game_queue(request: &Request, game_pool: &GamePool, pid: usize, bot: bool) -> Option<GameQueue> {
+    let id = |name| request.get_param(name).map(|id| id.parse::<usize>().ok());
+    let arena = request.get_param("arena").is_some();
+    if arena && !bot {
+        return None;
     }
+
+    let queue = match (id("rematch"), id("tournament")) {
+        (Some(rematch), _) => GameQueue {
+            room: game_pool.series.open(rematch?, pid)?.room.clone(),
+            rematch,
+            tournament: None,
+            arena: false,
+        },
+        (None, Some(tournament)) => GameQueue {
+            room: game_pool
+                .tournaments
+                .get(tournament?)
+                .filter(|tournament| tournament.has_match(pid))?
+                .room
+                .clone(),
+            rematch: None,
+            tournament,
+            arena: false,
+        },
+        (None, None) => GameQueue {
+            room: request.get_param("room"),
+            rematch: None,
+            tournament: None,
+            arena,
+        },
+    };
+    game_pool.config.room_rules(queue.room.as_deref())?;
+    Some(queue)
 }
 
-fn setup_logger() -> Result<(), fern::InitError> {
-    fern::Dispatch::new()
+/// The context of the winner and loser pages.
+fn result_context(
+    request: &Request,
+    game_pool: &Mutex<GamePool>,
+    accounts: &Mutex<Accounts>,
+) -> Context {
+    let mut context = Context::new();
+    let pid = match get_pid(request, accounts) {
+        Some(pid) => pid,
+        None => return context,
+    };
+
+    // Websocket tasks lock accounts before the pool, so they aren't locked together here
+    let (series, tournament) = {
+        let game_pool = game_pool.lock().unwrap();
+        (
+            game_pool
+                .series
+                .of_player(pid)
+                .map(|(id, series)| (id, series.clone())),
+            game_pool
+                .tournaments
+                .of_player(pid)
+                .map(|(id, tournament)| (id, tournament.name.clone())),
+        )
+    };
+
+    if let Some((id, series)) = series {
+        let accounts = accounts.lock().unwrap();
+        let mut players = series.players.clone();
+        players.sort_by_key(|&id| id != pid);
+        context.insert(
+            "series",
+            &SeriesEntry {
+                id,
+                games: series.games,
+                score: players
+                    .into_iter()
+                    .map(|id| SeriesScore {
+                        nickname: accounts.nickname(id),
+                        wins: series.wins.get(&id).copied().unwrap_or(0),
+                    })
+                    .collect(),
+                open: series.is_open(),
+            },
+        );
+    }
+    if let Some((id, name)) = tournament {
+        context.insert("tournament", &serde_json::json!({"id": id, "name": name}));
+    }
+    context
+}
+
+fn setup_logger(config: &Config) -> Result<(), fern::InitError> {
+    let mut dispatch = fern::Dispatch::new()
         .format(|out, message, record| {
             out.finish(format_args!(
                 "{}[{}] {}",
@@ -105,169 +458,770 @@ fn setup_logger() -> Result<(), fern::InitError> {
                 message
             ))
         })
-        .level(log::LevelFilter::Info)
-        .chain(std::io::stderr())
-        .apply()?;
+        .level(config.log_level())
+        .chain(std::io::stderr());
+    if let Some(path) = &config.log_file {
+        dispatch = dispatch.chain(fern::log_file(path)?);
+    }
+    dispatch.apply()?;
     Ok(())
 }
 
-fn main() {
-    setup_logger().unwrap();
-
-    let mut args = args();
-    args.next();
-    let addr = match args.next() {
-        Some(arg) => arg,
-        None => "127.0.0.1:8000".to_string(),
-    };
-
-    let domain = match args.next() {
-        Some(arg) => arg,
-        None => addr.clone(),
+#[tokio::main]
+async fn main() {
+    let config = match Config::from_args(args().skip(1), |name| var(name).ok()) {
+        Ok(Command::Run(config)) => config,
+        Ok(Command::PrintConfig(config)) => {
+            print!("{}", config.to_toml());
+            return;
+        }
+        Ok(Command::Help) => {
+            print!("{}", usage());
+            return;
+        }
+        Err(e) => {
+            eprintln!("poker-durak: {}\n\n{}", e, usage());
+            std::process::exit(2);
+        }
     };
+    setup_logger(&config).unwrap();
+    let config = Arc::new(config);
 
+    let (drain, drained) = mpsc::channel(1);
     let game_pool = Arc::new(Mutex::new(GamePool {
         players: HashSet::new(),
         players_channels: HashMap::new(),
-        players_time: HashMap::new(),
-        waiting_players: HashSet::new(),
+        waiting_players: HashMap::new(),
         on_delete: HashMap::new(),
+        games: HashMap::new(),
+        ratings: Arc::new(Mutex::new(Ratings::load())),
+        history: Arc::new(Mutex::new(History::load())),
+        arena: Arc::new(Mutex::new(Arena::load())),
+        series: SeriesPool::default(),
+        tournaments: Tournaments::load(),
+        config: Arc::clone(&config),
         counter: 0,
         playing: 0,
+        shutdown: watch::channel(None).0,
+        drain: Some(drain),
     }));
 
-    info!("Listening on {}", addr);
+    let accounts = Arc::new(Mutex::new(Accounts::load()));
+
+    let assets = Arc::new(Assets::load(config.static_dir.clone()));
 
-    rouille::start_server(&addr, move |request| {
-        router!(request,
-            (GET) (/ws) => {
+    let templates = match Templates::load(&assets) {
+        Ok(templates) => Arc::new(templates),
+        Err(e) => {
+            error!("TEMPLATES can't be loaded: {:?}", e);
+            return;
+        }
+    };
+
+    let addr = match config.addr.to_socket_addrs().map(|mut addrs| addrs.next()) {
+        Ok(Some(addr)) => addr,
+        _ => {
+            error!("Can't resolve {}", config.addr);
+            return;
+        }
+    };
+    info!("Listening on {}", config.addr);
+
+    let shutdown = drain_games(Arc::clone(&game_pool), drained);
+    let server = serve(addr, shutdown, move |request, ws| {
+        let request = &request;
+        let language = get_language(request);
+        let page = |name: &str, context: Context| -> Response {
+            match templates.render(name, language, context) {
+                Ok(page) => compress(request, Response::from_data(data_by_url(name), page)),
+                Err(e) => {
+                    warn!("TEMPLATE {} can't be rendered: {:?}", name, e);
+                    Response::empty(500)
+                }
+            }
+        };
+
+        let path = request.segments();
+        match (
+            request.method(),
+            &path.iter().map(String::as_str).collect::<Vec<_>>()[..],
+        ) {
+            ("GET", ["ws"]) => {
                 info!("GET /ws");
-                let sid = match get_sid(request) {
-                    Some(sid) => {
-                        info!("GAME SID {}", sid);
-                        sid
+                let player = get_pid(request, &accounts)
+                    .map(|pid| (pid, false))
+                    .or_else(|| get_bot_pid(request, &accounts).map(|pid| (pid, true)));
+                let (pid, bot) = match player {
+                    Some((pid, bot)) => {
+                        info!("GAME PID {}{}", pid, if bot { " (bot)" } else { "" });
+                        (pid, bot)
                     }
                     None => {
-                        let sid = thread_rng().gen::<usize>();
-                        info!("GAME SID {} NEW", sid);
-                        sid
+                        warn!("GET /ws 403");
+                        return Response::empty(403);
                     }
                 };
 
-                let (response, websocket) = try_or_400!(websocket::start(&request, Some("echo")));
+                let queue = match game_queue(request, &game_pool.lock().unwrap(), pid, bot) {
+                    Some(queue) => queue,
+                    None => {
+                        warn!("GET /ws game 404");
+                        return Response::empty_404();
+                    }
+                };
+
+                // Bots don't have to ask for the subprotocol of the browser client
+                let protocol = if bot && !asks_protocol(request, "echo") {
+                    None
+                } else {
+                    Some("echo")
+                };
+                let ws = try_or_400!(ws.ok_or(()));
                 let game_pool = Arc::clone(&game_pool);
+                let accounts = Arc::clone(&accounts);
 
-                thread::spawn(move || {
-                    websocket_handling_thread(websocket.recv().unwrap(), game_pool, sid);
+                websocket(ws, protocol, move |websocket| {
+                    websocket_handling(websocket, game_pool, accounts, pid, bot, queue, language)
+                })
+            }
+
+            ("GET", ["ws", "watch"]) => {
+                info!("GET /ws/watch");
+                let pid = match get_pid(request, &accounts) {
+                    Some(pid) => pid,
+                    None => {
+                        warn!("GET /ws/watch 403");
+                        return Response::empty(403);
+                    }
+                };
+
+                let ws = try_or_400!(ws.ok_or(()));
+                let game_pool = Arc::clone(&game_pool);
+                let accounts = Arc::clone(&accounts);
+
+                websocket(ws, Some("echo"), move |websocket| {
+                    spectator_handling(websocket, game_pool, accounts, pid)
+                })
+            }
+
+            ("POST", ["login"]) => {
+                info!("POST /login");
+                let fields = try_or_400!(request.form());
+                let nickname = form_field(&fields, "nickname");
+                let salt = accounts.lock().unwrap().salt(&nickname);
+                let password = Password::with_salt(&form_field(&fields, "password"), salt);
+                session_response(
+                    request,
+                    accounts.lock().unwrap().login(&nickname, &password),
+                )
+            }
+
+            ("POST", ["register"]) => {
+                info!("POST /register");
+                let fields = try_or_400!(request.form());
+                let result = Password::new(&form_field(&fields, "password")).and_then(|password| {
+                    let nickname = form_field(&fields, "nickname");
+                    accounts.lock().unwrap().register(&nickname, password)
                 });
+                session_response(request, result)
+            }
 
-                response
-            },
+            ("GET", ["api", "leaderboard"]) => {
+                info!("GET /api/leaderboard");
+                let leaderboard = game_pool
+                    .lock()
+                    .unwrap()
+                    .ratings
+                    .lock()
+                    .unwrap()
+                    .leaderboard();
+                let accounts = accounts.lock().unwrap();
+                compress(
+                    request,
+                    Response::json(
+                        &leaderboard
+                            .into_iter()
+                            .map(|(pid, rating)| LeaderboardEntry {
+                                nickname: accounts.nickname(pid),
+                                rating: rating.rating.round() as i64,
+                                games: rating.games,
+                                wins: rating.wins,
+                            })
+                            .collect::<Vec<_>>(),
+                    ),
+                )
+            }
 
-            (GET) (/{_any: String}) => {
-                let url = request.url();
+            ("GET", ["arena"]) => {
+                info!("GET /arena");
+                page("arena.html", Context::new())
+            }
 
-                match File::open("static".to_string() + router(&url)) {
-                    Ok(mut file) => {
-                        info!("GET {}", url);
-                        let mut data = Vec::new();
-                        file.read_to_end(&mut data).unwrap();
+            ("GET", ["api", "arena"]) => {
+                info!("GET /api/arena");
+                let bot = match request.get_param("bot") {
+                    Some(name) => Some(try_or_404!(accounts.lock().unwrap().pid(&name).ok_or(()))),
+                    None => None,
+                };
+                // Websocket tasks lock the arena after accounts, so it is copied out first
+                let (leaderboard, matches) = {
+                    let arena = Arc::clone(&game_pool.lock().unwrap().arena);
+                    let arena = arena.lock().unwrap();
+                    (
+                        arena.ratings.leaderboard(),
+                        arena
+                            .matches(bot, ARENA_MATCHES)
+                            .into_iter()
+                            .cloned()
+                            .collect::<Vec<_>>(),
+                    )
+                };
+                let accounts = accounts.lock().unwrap();
+                compress(
+                    request,
+                    Response::json(&ArenaView {
+                        leaderboard: leaderboard
+                            .into_iter()
+                            .map(|(pid, rating)| LeaderboardEntry {
+                                nickname: accounts.nickname(pid),
+                                rating: rating.rating.round() as i64,
+                                games: rating.games,
+                                wins: rating.wins,
+                            })
+                            .collect(),
+                        matches: matches
+                            .into_iter()
+                            .map(|m| ArenaMatchEntry {
+                                replay: m.replay,
+                                players: m
+                                    .players
+                                    .iter()
+                                    .map(|&pid| accounts.nickname(pid))
+                                    .collect(),
+                                winner: m.winner.map(|pid| accounts.nickname(pid)),
+                                finished: m.finished,
+                            })
+                            .collect(),
+                    }),
+                )
+            }
 
-                        let (all_games, now_games) = {
-                            let game_pool = game_pool.lock().unwrap();
-                            (game_pool.counter, game_pool.playing)
-                        };
+            ("GET", ["api", "games"]) => {
+                info!("GET /api/games");
+                // Websocket tasks lock accounts before the pool, so they aren't locked together here
+                let games = game_pool
+                    .lock()
+                    .unwrap()
+                    .games
+                    .iter()
+                    .map(|(&id, game)| (id, game.players.clone(), game.started))
+                    .collect::<Vec<_>>();
+                let accounts = accounts.lock().unwrap();
+                let mut games = games
+                    .into_iter()
+                    .map(|(id, players, started)| RunningGameEntry {
+                        id,
+                        players: players.iter().map(|&pid| accounts.nickname(pid)).collect(),
+                        duration: started.elapsed().as_secs(),
+                    })
+                    .collect::<Vec<_>>();
+                games.sort_by_key(|game| game.id);
+                compress(request, Response::json(&games))
+            }
 
+            ("GET", ["watch", id]) if id.parse::<usize>().is_ok() => {
+                info!("GET {}", request.url());
+                page("watch.html", Context::new())
+            }
 
-                        let resp = match String::from_utf8(data.clone()) {
-                            Ok(data) =>
-                                apply(request, Response::from_data(data_by_url(router(&url)), data
-                                    .replace("{host}", &domain)
-                                    .replace("{HEARTBIT_INTERVAL}", &(HEARTBIT_INTERVAL.as_secs().to_string()))
-                                    .replace("{all_games}", &all_games.to_string())
-                                    .replace("{now_games}", &now_games.to_string())
-                                )),
-                            Err(_) => {
-                                apply(request, Response::from_data(data_by_url(&url), data))
-                            }
+            ("GET", ["player", _]) => {
+                info!("GET {}", request.url());
+                page("player.html", Context::new())
+            }
+
+            ("GET", ["api", "player", name]) => {
+                info!("GET /api/player/{}", name);
+                let (ratings, history) = {
+                    let game_pool = game_pool.lock().unwrap();
+                    (
+                        Arc::clone(&game_pool.ratings),
+                        Arc::clone(&game_pool.history),
+                    )
+                };
+                let accounts = accounts.lock().unwrap();
+                let pid = try_or_404!(accounts.pid(name).ok_or(()));
+                let rating = ratings.lock().unwrap().get(pid).rating;
+                let history = history.lock().unwrap();
+
+                compress(
+                    request,
+                    Response::json(&PlayerProfile {
+                        nickname: accounts.nickname(pid),
+                        rating: rating.round() as i64,
+                        stats: history.player_stats(pid),
+                        games: history
+                            .player_games(pid)
+                            .into_iter()
+                            .map(|(id, game)| PlayerGameEntry {
+                                id,
+                                opponents: game
+                                    .players
+                                    .iter()
+                                    .filter(|&&id| id != pid)
+                                    .map(|&id| accounts.nickname(id))
+                                    .collect(),
+                                won: game.winner.map(|winner| winner == pid),
+                                duration: game.finished.saturating_sub(game.started),
+                                steps: game.steps.len(),
+                            })
+                            .collect(),
+                    }),
+                )
+            }
+
+            ("GET", ["replay", id]) if id.parse::<usize>().is_ok() => {
+                info!("GET {}", request.url());
+                page("replay.html", Context::new())
+            }
+
+            ("GET", ["api", "replay", id]) => {
+                let id: usize = try_or_404!(id.parse());
+                info!("GET /api/replay/{}", id);
+                let history = Arc::clone(&game_pool.lock().unwrap().history);
+                let history = history.lock().unwrap();
+                let game = try_or_404!(history.game(id).ok_or(()));
+                let accounts = accounts.lock().unwrap();
+
+                compress(
+                    request,
+                    Response::json(&Replay {
+                        players: game
+                            .players
+                            .iter()
+                            .map(|&pid| accounts.nickname(pid))
+                            .collect(),
+                        winner: game.winner.map(|pid| accounts.nickname(pid)),
+                        duration: game.finished.saturating_sub(game.started),
+                        hands: game
+                            .hands
+                            .iter()
+                            .map(|(pid, cards)| (accounts.nickname(*pid), cards))
+                            .collect(),
+                        steps: game
+                            .steps
+                            .iter()
+                            .map(|step| (accounts.nickname(step.pid), step))
+                            .collect(),
+                    }),
+                )
+            }
+
+            ("GET", ["api", "protocol", "schema"]) => {
+                info!("GET /api/protocol/schema");
+                compress(request, Response::json(&protocol_schema()))
+            }
+
+            ("GET", ["lang", code]) => {
+                info!("GET /lang/{}", code);
+                let language = try_or_404!(Language::from_code(code).ok_or(()));
+                let back = request
+                    .referer_path(config.host())
+                    .unwrap_or("/")
+                    .to_string();
+                Response::redirect_303(back).with_additional_header(
+                    "Set-Cookie",
+                    format!(
+                        "lang={}; Path=/; Max-Age=31536000; SameSite=Lax",
+                        language.code()
+                    ),
+                )
+            }
+
+            ("POST", ["logout"]) => {
+                info!("POST /logout");
+                if let Some(sid) = get_sid(request) {
+                    accounts.lock().unwrap().logout(&sid);
+                }
+                Response::redirect_303("/")
+                    .with_additional_header("Set-Cookie", "sid=; Path=/; HttpOnly; Max-Age=0")
+            }
+
+            ("GET", []) => {
+                info!("GET /");
+                let rooms = config
+                    .rooms
+                    .iter()
+                    .map(|(name, time_control)| RoomEntry { name, time_control })
+                    .collect::<Vec<_>>();
+                let mut context = Context::new();
+                context.insert("rooms", &rooms);
+                page("index.html", context)
+            }
+
+            ("GET", ["about"]) => {
+                info!("GET /about");
+                let mut context = Context::new();
+                context.insert("hand_size", &config.rules.hand_size.to_string());
+                page("about.html", context)
+            }
+
+            ("GET", ["stat"]) => {
+                info!("GET /stat");
+                let mut context = Context::new();
+                {
+                    let game_pool = game_pool.lock().unwrap();
+                    context.insert("all_games", &game_pool.counter);
+                    context.insert("now_games", &game_pool.playing);
+                }
+                page("stat.html", context)
+            }
+
+            ("GET", ["game"]) => {
+                let pid = match get_pid(request, &accounts) {
+                    Some(pid) => pid,
+                    None => {
+                        info!("GET /game unauthorized");
+                        return Response::redirect_303("/login");
+                    }
+                };
+                info!("GET /game");
+                let queue =
+                    try_or_404!(
+                        game_queue(request, &game_pool.lock().unwrap(), pid, false).ok_or(())
+                    );
+                let rules = config.room_rules(queue.room.as_deref()).unwrap();
+                let mut context = Context::new();
+                context.insert("host", config.host());
+                context.insert("heartbit_interval", &config.heartbit_interval);
+                context.insert("room", &queue.room);
+                context.insert("rematch", &queue.rematch);
+                context.insert("tournament", &queue.tournament);
+                context.insert("time_control", &rules.time_control);
+                page("game.html", context)
+            }
+
+            ("GET", ["winner"]) => {
+                info!("GET /winner");
+                page(
+                    "winner.html",
+                    result_context(request, &game_pool, &accounts),
+                )
+            }
+
+            ("GET", ["loser"]) => {
+                info!("GET /loser");
+                page("loser.html", result_context(request, &game_pool, &accounts))
+            }
+
+            ("GET", ["login"]) => {
+                info!("GET /login");
+                page("login.html", Context::new())
+            }
+
+            ("GET", ["leaderboard"]) => {
+                info!("GET /leaderboard");
+                page("leaderboard.html", Context::new())
+            }
+
+            ("GET", ["games"]) => {
+                info!("GET /games");
+                page("games.html", Context::new())
+            }
+
+            ("GET", ["tournaments"]) => {
+                info!("GET /tournaments");
+                let game_pool = game_pool.lock().unwrap();
+                let tournaments = game_pool
+                    .tournaments
+                    .list()
+                    .into_iter()
+                    .map(|(id, tournament)| TournamentEntry {
+                        id,
+                        name: &tournament.name,
+                        format: tournament.format,
+                        room: &tournament.room,
+                        best_of: tournament.best_of,
+                        stage: tournament.stage,
+                        players: tournament.players.len(),
+                    })
+                    .collect::<Vec<_>>();
+                let mut context = Context::new();
+                context.insert("tournaments", &tournaments);
+                context.insert("rooms", &config.rooms.keys().collect::<Vec<_>>());
+                context.insert("max_best_of", &MAX_BEST_OF);
+                context.insert("max_name_length", &MAX_NAME_LENGTH);
+                page("tournaments.html", context)
+            }
+
+            ("POST", ["tournaments"]) => {
+                info!("POST /tournaments");
+                let pid = match get_pid(request, &accounts) {
+                    Some(pid) => pid,
+                    None => return Response::redirect_303("/login"),
+                };
+                let fields = try_or_400!(request.form());
+                let room = Some(form_field(&fields, "room")).filter(|room| !room.is_empty());
+                let tournament = Format::from_name(&form_field(&fields, "format"))
+                    .ok_or(TournamentError::InvalidFormat)
+                    .and_then(|format| match config.room_rules(room.as_deref()) {
+                        Some(_) => Ok(format),
+                        None => Err(TournamentError::InvalidRoom),
+                    })
+                    .and_then(|format| {
+                        let best_of = form_field(&fields, "best_of")
+                            .parse()
+                            .map_err(|_| TournamentError::InvalidBestOf)?;
+                        Tournament::new(&form_field(&fields, "name"), pid, format, room, best_of)
+                    });
+                match tournament {
+                    Ok(tournament) => {
+                        let id = game_pool.lock().unwrap().tournaments.create(tournament);
+                        info!("TOURNAMENT {} created by {}", id, pid);
+                        Response::redirect_303(format!("/tournament/{}", id))
+                    }
+                    Err(e) => {
+                        info!("TOURNAMENT error {}", e);
+                        Response::redirect_303(format!("/tournaments?error={:?}", e))
+                    }
+                }
+            }
+
+            ("GET", ["tournament", id]) => {
+                let id: usize = try_or_404!(id.parse());
+                info!("GET /tournament/{}", id);
+                let pid = get_pid(request, &accounts);
+                let tournament = game_pool.lock().unwrap().tournaments.get(id).cloned();
+                let tournament = try_or_404!(tournament.ok_or(()));
+                let mut context = Context::new();
+                context.insert(
+                    "tournament",
+                    &tournament_view(id, &tournament, &accounts.lock().unwrap(), pid),
+                );
+                page("tournament.html", context)
+            }
+
+            ("GET", ["api", "tournament", id]) => {
+                let id: usize = try_or_404!(id.parse());
+                info!("GET /api/tournament/{}", id);
+                let pid = get_pid(request, &accounts);
+                let tournament = game_pool.lock().unwrap().tournaments.get(id).cloned();
+                let tournament = try_or_404!(tournament.ok_or(()));
+                let view = tournament_view(id, &tournament, &accounts.lock().unwrap(), pid);
+                compress(request, Response::json(&view))
+            }
+
+            ("POST", ["tournament", id, "join"]) => {
+                let id: usize = try_or_404!(id.parse());
+                info!("POST /tournament/{}/join", id);
+                let pid = match get_pid(request, &accounts) {
+                    Some(pid) => pid,
+                    None => return Response::redirect_303("/login"),
+                };
+                let result = game_pool
+                    .lock()
+                    .unwrap()
+                    .tournaments
+                    .update(id, |tournament| tournament.join(pid));
+                tournament_response(id, result)
+            }
+
+            ("POST", ["tournament", id, "start"]) => {
+                let id: usize = try_or_404!(id.parse());
+                info!("POST /tournament/{}/start", id);
+                let pid = match get_pid(request, &accounts) {
+                    Some(pid) => pid,
+                    None => return Response::redirect_303("/login"),
+                };
+                let mut game_pool = game_pool.lock().unwrap();
+                let ratings = Arc::clone(&game_pool.ratings);
+                let ratings = ratings.lock().unwrap();
+                let result = game_pool.tournaments.update(id, |tournament| {
+                    tournament.start(pid, |id| ratings.get(id).rating)
+                });
+                tournament_response(id, result)
+            }
+
+            ("GET", ["bots"]) => {
+                info!("GET /bots");
+                let pid = match get_pid(request, &accounts) {
+                    Some(pid) => pid,
+                    None => return Response::redirect_303("/login"),
+                };
+                page(
+                    "bots.html",
+                    bots_context(&accounts.lock().unwrap(), pid, config.host(), None),
+                )
+            }
+
+            ("POST", ["bots"]) => {
+                info!("POST /bots");
+                let pid = match get_pid(request, &accounts) {
+                    Some(pid) => pid,
+                    None => return Response::redirect_303("/login"),
+                };
+                let fields = try_or_400!(request.form());
+                let nickname = form_field(&fields, "nickname").trim().to_string();
+                let mut accounts = accounts.lock().unwrap();
+                match accounts.register_bot(pid, &nickname) {
+                    Ok(token) => {
+                        let token = BotToken { nickname, token };
+                        page(
+                            "bots.html",
+                            bots_context(&accounts, pid, config.host(), Some(token)),
+                        )
+                    }
+                    Err(e) => {
+                        info!("ACCOUNT error {}", e);
+                        Response::redirect_303(format!("/bots?error={:?}", e))
+                    }
+                }
+            }
+
+            ("POST", ["bots", nickname, "token"]) => {
+                info!("POST /bots/{}/token", nickname);
+                let pid = match get_pid(request, &accounts) {
+                    Some(pid) => pid,
+                    None => return Response::redirect_303("/login"),
+                };
+                let mut accounts = accounts.lock().unwrap();
+                match accounts.reset_token(pid, nickname) {
+                    Ok(token) => {
+                        let token = BotToken {
+                            nickname: nickname.to_string(),
+                            token,
                         };
+                        page(
+                            "bots.html",
+                            bots_context(&accounts, pid, config.host(), Some(token)),
+                        )
+                    }
+                    Err(e) => {
+                        info!("ACCOUNT error {}", e);
+                        Response::redirect_303(format!("/bots?error={:?}", e))
+                    }
+                }
+            }
 
-                        if router(&url) == "/game.html" {
-                            apply(request, set_cookies(&request, resp))
-                        } else {
-                            resp
-                        }
+            ("GET", _) => {
+                let url = request.url();
+
+                if url == "/sitemap.txt" {
+                    info!("GET {}", url);
+                    let mut context = Context::new();
+                    context.insert("host", config.host());
+                    return page("sitemap.txt", context);
+                }
+
+                match assets.get(&url) {
+                    Some(asset) => {
+                        info!("GET {}", url);
+                        compress(request, asset.into_response(request))
                     }
-                    Err(_) => {
+                    None => {
                         warn!("GET {} 404", url);
-                        apply(request, rouille::Response::from_file("text/html", File::open("static/404.html").unwrap()).with_status_code(404))
+                        page("404.html", Context::new()).with_status_code(404)
                     }
                 }
-
-            },
+            }
             _ => {
                 warn!("{} {} 400", request.method(), request.url());
-                apply(request, rouille::Response::empty_400())
+                compress(request, Response::empty_400())
             }
-        )
+        }
     });
+    match server.await {
+        Ok(()) => info!("Stopped"),
+        Err(e) => error!("Can't listen on {}: {}", addr, e),
+    }
 }
 
-fn websocket_next(
-    mut websocket: websocket::Websocket,
-) -> Option<(websocket::Websocket, websocket::Message)> {
-    let (ft, fr) = mpsc::channel();
+/// Waits for SIGTERM or Ctrl+C.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
 
-    thread::spawn(move || {
-        let now = Instant::now();
-        ft.send(loop {
-            match websocket.try_recv() {
-                Ok(msg) => {
-                    break Some((websocket, msg));
-                }
-                Err(websocket::WebsocketRecvError::Empty) => (),
-                Err(_) => {
-                    break None;
+        let mut terminate = signal(SignalKind::terminate()).unwrap();
+        tokio::select! {
+            _ = terminate.recv() => info!("SHUTDOWN on SIGTERM"),
+            _ = tokio::signal::ctrl_c() => info!("SHUTDOWN on Ctrl+C"),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await.ok();
+        info!("SHUTDOWN on Ctrl+C");
+    }
+}
+
+/// Returns once every game is recorded, so that disconnected players can come
+/// back until then.
+async fn drain_games(game_pool: Arc<Mutex<GamePool>>, mut drained: mpsc::Receiver<()>) {
+    shutdown_signal().await;
+    let deadline = {
+        let mut game_pool = game_pool.lock().unwrap();
+        let deadline = Instant::now() + game_pool.config.shutdown_wait();
+        info!("SHUTDOWN waiting for {} games", game_pool.games.len());
+        game_pool.shutdown.send_replace(Some(deadline));
+        game_pool.drain = None;
+        wake_waiting(&game_pool, |_| true);
+        deadline
+    };
+
+    if timeout_at(deadline.into(), drained.recv()).await.is_err() {
+        let game_pool = game_pool.lock().unwrap();
+        for (gid, game) in game_pool.games.iter() {
+            warn!("SHUTDOWN stopping GAME {}", gid);
+            game.requests
+                .send((game.players[0], GameRequest::Stop))
+                .ok();
+        }
+    }
+    drained.recv().await;
+    info!("SHUTDOWN every game is recorded");
+}
+
+/// The `ServerShutdown` message, `None` while the server is running.
+fn shutdown_message(game_pool: &GamePool) -> Option<ServerMessage> {
+    let deadline = (*game_pool.shutdown.borrow())?;
+    Some(ServerMessage::ServerShutdown {
+        wait: deadline.saturating_duration_since(Instant::now()).as_secs(),
+    })
+}
+
+/// Fails if nothing, not even a ping, came during `heartbit_interval`.
+async fn websocket_next(
+    websocket: &mut WebSocket,
+    last_message: &mut Instant,
+    heartbit_interval: Duration,
+) -> Result<Message, ()> {
+    loop {
+        let deadline = (*last_message + heartbit_interval).into();
+        match timeout_at(deadline, websocket.next()).await {
+            Ok(Some(Ok(message))) if !message.is_close() => {
+                *last_message = Instant::now();
+                if !message.is_ping() && !message.is_pong() {
+                    return Ok(message);
                 }
             }
-            sleep(WS_UPDATE);
-            if now.elapsed() > HEARTBIT_INTERVAL {
-                break None;
-            }
-        })
-        .unwrap();
-    });
+            _ => return Err(()),
+        }
+    }
+}
 
-    fr.recv().ok()?
+async fn send_response(websocket: &mut WebSocket, response: &ServerMessage) {
+    websocket
+        .send(Message::text(serde_json::to_string(response).unwrap()))
+        .await
+        .ok();
 }
 
-#[derive(Serialize)]
-#[allow(clippy::large_enum_variant)]
-enum JsonResponse {
-    Pong,
-    ID(usize),
-    YouArePlaying,
-    YourCards(HashSet<Card>, usize),
-    YourTurn(State, HashSet<Card>, usize, usize, u64),
-    YouMadeStep(State, HashSet<Card>, usize, usize),
-    StepError(StepError),
-    Message(String),
-    Sent(Result<(), ()>),
-    JsonError,
-    GameWinner,
-    GameLoser,
-}
-
-#[derive(Deserialize)]
-enum JsonRequest {
-    Ping,
-    MakeStep(Step),
-    SendMessage(String),
-    Exit,
-}
-
-fn player_init(game_pool: Arc<Mutex<GamePool>>, pid: usize) -> (bool, Option<GameChannelClient>) {
-    sleep(PLAYING_ACTIVITY_WAIT);
+async fn player_init(
+    game_pool: Arc<Mutex<GamePool>>,
+    pid: usize,
+    queue: GameQueue,
+) -> (bool, Option<PlayerGame>) {
+    sleep(PLAYING_ACTIVITY_WAIT).await;
 
     let mut game_pool = game_pool.lock().unwrap();
 
@@ -278,339 +1232,938 @@ fn player_init(game_pool: Arc<Mutex<GamePool>>, pid: usize) -> (bool, Option<Gam
     } else if game_pool.players.contains(&pid) {
         (true, None)
     } else {
-        game_pool.waiting_players.insert(pid);
+        // Another connection of the player may be waiting already
+        let wake = game_pool
+            .waiting_players
+            .get(&pid)
+            .map_or_else(Default::default, |waiting| Arc::clone(&waiting.wake));
+        game_pool.waiting_players.insert(
+            pid,
+            WaitingPlayer {
+                since: Instant::now(),
+                queue,
+                wake,
+            },
+        );
         info!("PLAYER {} registrated!", pid);
         (false, None)
     }
 }
 
+/// Takes the player out of matchmaking. A game created for the player in the
+/// meantime is left at once, under the lock `game_create` pairs players with.
+fn stop_waiting(game_pool: Arc<Mutex<GamePool>>, pid: usize) {
+    let game = {
+        let mut game_pool = game_pool.lock().unwrap();
+        game_pool.waiting_players.remove(&pid);
+        let game = game_pool.players_channels.remove(&pid);
+        if game.is_some() {
+            game_pool.players.remove(&pid);
+        }
+        game
+    };
+    if let Some(game) = game {
+        info!("PLAYER {} left GAME {} before it started", pid, game.gid);
+        game_exit(game_pool, Some(game), None, Some(true), pid);
+    }
+}
+
 fn game_exit(
     game_pool: Arc<Mutex<GamePool>>,
-    game: Option<GameChannelClient>,
-    websocket: Option<websocket::Websocket>,
+    game: Option<PlayerGame>,
+    websocket: Option<WebSocket>,
     ws_end_success: Option<bool>,
     pid: usize,
 ) {
-    thread::spawn(move || {
+    tokio::spawn(async move {
         game_pool.lock().unwrap().on_delete.insert(pid, game);
 
-        if ws_end_success == None {
-            game_pool.lock().unwrap().waiting_players.remove(&pid);
-        } else if ws_end_success == Some(false) {
+        if ws_end_success == Some(false) {
             info!("PLAYER {} disconnected", pid);
-            sleep(WS_CLOSED_WAIT);
+            let ws_closed_wait = game_pool.lock().unwrap().config.ws_closed_wait();
+            sleep(ws_closed_wait).await;
         }
 
-        let mut game_pool = game_pool.lock().unwrap();
-
-        if game_pool.on_delete.contains_key(&pid) {
-            if ws_end_success != None {
-                let mut game = game_pool.on_delete.remove(&pid).unwrap().unwrap();
-                info!("PLAYER {} is exiting!", pid);
-
-                game.kick_me();
+        // The player may have come back to the game while the websocket was closed
+        let game = match game_pool.lock().unwrap().on_delete.remove(&pid) {
+            Some(game) => game,
+            None => return,
+        };
 
-                if game.game_winner() == Some(pid) {
-                    if let Some(mut websocket) = websocket {
-                        websocket
-                            .send_text(&serde_json::to_string(&JsonResponse::GameWinner).unwrap())
-                            .ok();
-                    };
-                } else if let Some(mut websocket) = websocket {
-                    websocket
-                        .send_text(&serde_json::to_string(&JsonResponse::GameLoser).unwrap())
-                        .ok();
+        if let (
+            Some(PlayerGame {
+                gid,
+                client: mut game,
+            }),
+            Some(_),
+        ) = (game, ws_end_success)
+        {
+            info!("PLAYER {} is exiting!", pid);
+
+            // The game worker may have exited already, then the game is over
+            game.kick_me().ok();
+            let view = game.get_view().await.ok();
+
+            if let Some(view) = &view {
+                let mut game_pool = game_pool.lock().unwrap();
+                if game_pool.series.finish(gid, view.first_player, view.winner) {
+                    info!("GAME {} counted in the series", gid);
                 }
-
-                if game.exit() {
-                    game_pool.playing -= 1;
+                if let Some(id) = game_pool.tournaments.game_finished(gid, view.winner) {
+                    info!("GAME {} counted in tournament {}", gid, id);
                 }
+            }
 
-                if game_pool.players.contains(&pid) {
-                    game_pool.players.remove(&pid);
-                    game_pool.players_channels.remove(&pid);
-                    game_pool.players_time.remove(&pid);
+            if let Some(mut websocket) = websocket {
+                // A stopped game has no result
+                if let Some(view) = &view {
+                    let result = if view.winner == Some(pid) {
+                        ServerMessage::GameWinner
+                    } else {
+                        ServerMessage::GameLoser
+                    };
+                    send_response(&mut websocket, &result).await;
                 }
+                websocket.close().await.ok();
             }
-            game_pool.on_delete.remove(&pid);
 
-            info!("PLAYER {} exited!", pid);
+            let last = game.exit().await.unwrap_or(true);
+            let mut game_pool = game_pool.lock().unwrap();
+            if last && game_pool.games.remove(&gid).is_some() {
+                game_pool.playing -= 1;
+                // The game may have freed a place or decided a tournament pairing
+                let max_games = game_pool.config.max_games;
+                let was_full = max_games > 0 && game_pool.playing + 1 >= max_games;
+                wake_waiting(&game_pool, |waiting| {
+                    was_full || waiting.queue.tournament.is_some()
+                });
+            }
+
+            if game_pool.players.contains(&pid) {
+                game_pool.players.remove(&pid);
+                game_pool.players_channels.remove(&pid);
+            }
         }
+
+        info!("PLAYER {} exited!", pid);
     });
 }
 
-fn game_create(game_pool: &mut GamePool) {
-    let players = game_pool
+/// Wakes the waiting players matching the predicate.
+fn wake_waiting(game_pool: &GamePool, predicate: impl Fn(&WaitingPlayer) -> bool) {
+    for waiting in game_pool.waiting_players.values().filter(|&w| predicate(w)) {
+        waiting.wake.notify_one();
+    }
+}
+
+/// The acceptable rating difference grows with the time both players have been
+/// waiting. Without an opponent, returns when the difference to the closest
+/// player left out becomes acceptable.
+fn find_opponent(game_pool: &GamePool, pid: usize) -> Result<usize, Option<Instant>> {
+    let ratings = game_pool.ratings.lock().unwrap();
+    let rating = ratings.get(pid).rating;
+    let waiting = game_pool.waiting_players.get(&pid).ok_or(None)?;
+    let any_rating = waiting.queue.rematch.is_some()
+        || waiting.queue.tournament.is_some()
+        || waiting.queue.arena;
+
+    let (accepted, left_out): (Vec<_>, Vec<_>) = game_pool
         .waiting_players
         .iter()
-        .copied()
-        .collect::<Vec<_>>();
+        .filter(|&(&id, opponent)| id != pid && opponent.queue == waiting.queue)
+        .filter(|&(&id, _)| match waiting.queue.tournament {
+            Some(tournament) => game_pool
+                .tournaments
+                .get(tournament)
+                .is_some_and(|tournament| tournament.opponent(pid) == Some(id)),
+            None => true,
+        })
+        .map(|(&id, opponent)| {
+            let diff = (ratings.get(id).rating - rating).abs();
+            // The window grows from the start of the longer wait
+            let since = waiting.since.min(opponent.since);
+            let accepted_at = if any_rating || diff <= RATING_WINDOW {
+                since
+            } else {
+                since + Duration::from_secs_f64((diff - RATING_WINDOW) / RATING_WINDOW_GROWTH)
+            };
+            (id, diff, accepted_at)
+        })
+        .partition(|&(_, _, accepted_at)| accepted_at <= Instant::now());
+
+    accepted
+        .into_iter()
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .map(|(id, _, _)| id)
+        .ok_or_else(|| left_out.into_iter().map(|(_, _, at)| at).min())
+}
+
+fn game_create(game_pool: &mut GamePool, players: Vec<usize>, rules: Rules, queue: GameQueue) {
     game_pool.counter += 1;
     let counter = game_pool.counter;
     game_pool.playing += 1;
 
     info!("GAME {} created", counter);
 
-    let mut now_playing = HashMap::new();
+    let series = &mut game_pool.series;
+    let rematch = queue
+        .rematch
+        .and_then(|id| Some((id, series.rematch(id, counter)?.next_first_player())));
+    let first_player = match rematch {
+        Some((id, first_player)) => {
+            info!("GAME {} is a rematch in series {}", counter, id);
+            first_player
+        }
+        None => {
+            match queue.tournament {
+                Some(id) if game_pool.tournaments.game_started(id, &players, counter) => {
+                    info!("GAME {} is a match in tournament {}", counter, id);
+                    game_pool.series.leave(&players);
+                }
+                _ if queue.arena => {
+                    info!("GAME {} is an arena game", counter);
+                    game_pool.series.leave(&players);
+                }
+                _ => {
+                    game_pool.series.start(&players, queue.room, counter);
+                }
+            }
+            None
+        }
+    };
+
+    let mut events = HashMap::new();
 
-    let (cltt, srvr) = mpsc::channel();
+    let (cltt, srvr) = mpsc::unbounded_channel();
 
-    for player in players {
+    for &player in players.iter() {
+        if let Some(waiting) = game_pool.waiting_players.remove(&player) {
+            waiting.wake.notify_one();
+        }
         game_pool.players.insert(player);
-        let (srvt, cltr) = mpsc::channel();
-        now_playing.insert(player, srvt);
+        let (evtt, evtr) = mpsc::unbounded_channel();
+        events.insert(player, evtt);
         game_pool.players_channels.insert(
             player,
-            GameChannelClient(mpsc::Sender::clone(&cltt), cltr, player),
+            PlayerGame {
+                gid: counter,
+                client: GameChannelClient(cltt.clone(), player, evtr),
+            },
         );
-        game_pool.players_time.insert(player, None);
     }
-    game_pool.waiting_players.clear();
 
-    let counter: usize = game_pool.counter;
-    thread::spawn(move || game_worker(now_playing, srvr, counter));
+    game_pool.games.insert(
+        counter,
+        RunningGame {
+            players,
+            started: Instant::now(),
+            requests: cltt,
+        },
+    );
+
+    let ratings = Arc::clone(&game_pool.ratings);
+    let history = Arc::clone(&game_pool.history);
+    let arena = if queue.arena {
+        Some(Arc::clone(&game_pool.arena))
+    } else {
+        None
+    };
+    let drain = game_pool.drain.clone();
+    tokio::spawn(async move {
+        let result = game_worker(events, srvr, counter, rules, first_player).await;
+        info!(
+            "GAME {} result: players {:?}, winner {:?}",
+            counter, result.players, result.winner
+        );
+        // Results are saved to files, which blocks
+        let recorded = tokio::task::spawn_blocking(move || {
+            let replay = match arena {
+                // Arena games are rated on the bot leaderboard only
+                Some(arena) => {
+                    let replay = history.lock().unwrap().record(result.clone());
+                    arena.lock().unwrap().record(replay, &result);
+                    replay
+                }
+                None => {
+                    ratings.lock().unwrap().record(&result);
+                    history.lock().unwrap().record(result)
+                }
+            };
+            info!("GAME {} recorded as replay {}", counter, replay);
+        });
+        recorded.await.ok();
+        // The server shutting down waits for this
+        drop(drain);
+    });
 }
 
-fn wait_game(
-    mut websocket: websocket::Websocket,
+async fn wait_game(
+    mut websocket: WebSocket,
     game_pool: Arc<Mutex<GamePool>>,
     pid: usize,
-) -> Option<websocket::Websocket> {
+    rate_limit: &mut Option<RateLimit>,
+) -> Option<WebSocket> {
+    let mut last_message = Instant::now();
+    let heartbit_interval = game_pool.lock().unwrap().config.heartbit_interval();
+
     loop {
-        if game_pool.lock().unwrap().players.contains(&pid) {
-            break;
-        }
+        let (shutdown, wake, retry) = {
+            let mut game_pool = game_pool.lock().unwrap();
+            if game_pool.players.contains(&pid) {
+                break;
+            }
+            let (queue, wake) = match game_pool.waiting_players.get(&pid) {
+                Some(waiting) => (waiting.queue.clone(), Arc::clone(&waiting.wake)),
+                // Another connection of the player has stopped waiting, this
+                // one is closed by dropping it
+                None => return None,
+            };
+            let max_games = game_pool.config.max_games;
+            let shutdown = shutdown_message(&game_pool);
+            let mut retry = None;
+            if shutdown.is_some() {
+                // A server shutting down starts no games
+            } else if max_games > 0 && game_pool.playing >= max_games {
+                // Players keep waiting until some game is over
+            } else {
+                match find_opponent(&game_pool, pid) {
+                    Ok(opponent) => {
+                        let rules = game_pool.config.room_rules(queue.room.as_deref()).unwrap();
+                        game_create(&mut game_pool, vec![pid, opponent], rules, queue);
+                        break;
+                    }
+                    Err(at) => retry = at,
+                }
+            }
+            (shutdown, wake, retry)
+        };
 
-        let ans = websocket_next(websocket);
+        if let Some(shutdown) = shutdown {
+            info!(
+                "PLAYER {} won't get a game, the server is shutting down",
+                pid
+            );
+            send_response(&mut websocket, &shutdown).await;
+            websocket.close().await.ok();
+            stop_waiting(game_pool, pid);
+            return None;
+        }
 
-        websocket = match ans {
-            None => {
-                game_exit(game_pool, None, None, None, pid);
-                return None;
-            }
-            Some((mut websocket, message)) => {
-                if let websocket::Message::Text(txt) = message {
-                    if let Ok(req) = serde_json::from_str::<JsonRequest>(&txt) {
-                        if let JsonRequest::Ping = req {
-                            websocket
-                                .send_text(&serde_json::to_string(&JsonResponse::Pong).unwrap())
-                                .ok();
+        tokio::select! {
+            message = websocket_next(&mut websocket, &mut last_message, heartbit_interval) => {
+                match message.as_ref().map(|message| message.to_str()) {
+                    Err(()) => {
+                        stop_waiting(game_pool, pid);
+                        return None;
+                    }
+                    Ok(Ok(txt)) => {
+                        if rate_limited(&mut websocket, rate_limit).await {
+                            info!("PLAYER {} is rate limited", pid);
+                        } else if let Ok(ClientMessage::Ping) = serde_json::from_str(txt) {
+                            send_response(&mut websocket, &ServerMessage::Pong).await;
                         }
                     }
+                    Ok(Err(())) => (),
                 }
-                websocket
             }
-        };
+            // Woken by the opponent starting the game or when things change
+            _ = wake.notified() => (),
+            _ = sleep_until(retry.unwrap_or_else(Instant::now).into()), if retry.is_some() => (),
+        }
     }
     Some(websocket)
 }
 
-fn refresh_time(
-    game: &mut GameChannelClient,
-    stepping_time: &mut Option<Instant>,
-    your_turn_new: &mut bool,
-    pid: usize,
-) -> Result<Option<String>, ()> {
-    let stepping_player = game.get_stepping_player();
-    if stepping_player == pid && *your_turn_new {
-        if stepping_time.is_none() {
-            *stepping_time = Some(Instant::now());
-        }
-        let time_elapsed = stepping_time.unwrap().elapsed().as_secs();
-
-        let msg = serde_json::to_string(&JsonResponse::YourTurn(
-            game.get_state_cards(),
-            game.get_my_cards(),
-            game.get_deck_size(),
-            game.players_decks()[0],
-            TIMEOUT.as_secs() - time_elapsed,
-        ))
-        .unwrap();
+/// Only bots get the list of legal steps.
+fn your_turn(view: &PlayerView, your_turn_new: &mut bool, bot: bool) -> Option<ServerMessage> {
+    if *your_turn_new && view.stepping_player == view.pid {
         *your_turn_new = false;
-        return Ok(Some(msg));
-    } else if stepping_player == pid {
-        if let Some(stepping_time) = stepping_time {
-            if stepping_time.elapsed() > TIMEOUT {
-                return Err(());
+        Some(ServerMessage::your_turn(view, bot))
+    } else {
+        None
+    }
+}
+
+fn game_snapshot(view: &PlayerView, accounts: &Mutex<Accounts>) -> ServerMessage {
+    let accounts = accounts.lock().unwrap();
+    ServerMessage::game_snapshot(view, |id| accounts.nickname(id))
+}
+
+fn spectator_view(view: SpectatorView, accounts: &Mutex<Accounts>) -> ServerMessage {
+    let accounts = accounts.lock().unwrap();
+
+    ServerMessage::SpectatorView {
+        board: BoardView::from_state(&view.state),
+        deck_size: view.deck_size,
+        turn_seat: view
+            .seats
+            .iter()
+            .find(|seat| seat.pid == view.stepping_player)
+            .map_or(0, |seat| seat.seat),
+        players: view
+            .seats
+            .iter()
+            .map(|seat| SeatView {
+                seat: seat.seat,
+                nickname: accounts.nickname(seat.pid),
+                cards: seat.cards,
+                playing: seat.playing,
+                clock: seat.clock.map(|clock| clock.as_secs()),
+            })
+            .collect(),
+        steps: view.steps,
+        status: GameStatus::new(view.winner, |id| accounts.nickname(id)),
+        hands: view.hands.map(|hands| {
+            hands
+                .into_iter()
+                .map(|(pid, cards)| RevealedHand {
+                    nickname: accounts.nickname(pid),
+                    cards,
+                })
+                .collect()
+        }),
+    }
+}
+
+/// Describes a rejected step in the player's language.
+fn step_error(
+    error: StepError,
+    step: StepMessage,
+    view: &PlayerView,
+    accounts: &Mutex<Accounts>,
+    language: Language,
+) -> ServerMessage {
+    let context = match error {
+        StepError::InvalidPID => Some(StepErrorContext::NotYourTurn {
+            stepping: accounts.lock().unwrap().nickname(view.stepping_player),
+        }),
+        StepError::InvalidStepType => Some(StepErrorContext::AllowedSteps {
+            steps: match view.state {
+                State::Passive if view.deck_size > 0 => {
+                    vec![StepKind::GetCard, StepKind::GiveComb]
+                }
+                State::Passive => vec![StepKind::GiveComb],
+                State::Active(_) => vec![StepKind::TransComb, StepKind::GetComb],
+            },
+        }),
+        StepError::InvalidCards => {
+            let cards = match step {
+                StepMessage::GiveComb { cards } | StepMessage::TransComb { cards } => cards,
+                _ => HashSet::new(),
+            };
+            let missing = cards
+                .into_iter()
+                .filter(|card| match &view.state {
+                    State::Passive => !view.cards.contains(card),
+                    State::Active(board) => {
+                        !view.cards.contains(card) && !board.cards.contains(card)
+                    }
+                })
+                .collect::<HashSet<_>>();
+            if missing.is_empty() {
+                None
+            } else {
+                Some(StepErrorContext::MissingCards { cards: missing })
             }
         }
+        StepError::InvalidComb => None,
+        StepError::WeakComb => {
+            BoardView::from_state(&view.state).map(|board| StepErrorContext::CombToBeat { board })
+        }
+    };
+
+    ServerMessage::StepError {
+        error: error.into(),
+        message: error.localize(language).to_string(),
+        context,
     }
+}
 
-    if game.game_winner().is_some() {
-        return Err(());
+/// Returns `true` if the message is dropped.
+async fn rate_limited(websocket: &mut WebSocket, rate_limit: &mut Option<RateLimit>) -> bool {
+    if rate_limit.as_mut().is_none_or(|limit| limit.check()) {
+        return false;
     }
-    Ok(None)
+    send_response(websocket, &ServerMessage::RateLimited).await;
+    true
 }
 
-fn websocket_handling_thread(
-    mut websocket: websocket::Websocket,
+/// Waits for the client's `Hello` and checks the protocol version.
+async fn handshake(
+    websocket: &mut WebSocket,
+    heartbit_interval: Duration,
+    rate_limit: &mut Option<RateLimit>,
+) -> Result<u32, ()> {
+    let mut last_message = Instant::now();
+
+    let message = loop {
+        let message = websocket_next(websocket, &mut last_message, heartbit_interval).await?;
+        if message.is_text() && !rate_limited(websocket, rate_limit).await {
+            break message;
+        }
+    };
+
+    let reason = match serde_json::from_str(message.to_str()?) {
+        Ok(ClientMessage::Hello { version })
+            if (MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&version) =>
+        {
+            return Ok(version);
+        }
+        Ok(ClientMessage::Hello { .. }) => ProtocolErrorReason::UnsupportedVersion {
+            min: MIN_PROTOCOL_VERSION,
+            max: PROTOCOL_VERSION,
+        },
+        _ => ProtocolErrorReason::HandshakeExpected,
+    };
+    send_response(websocket, &ServerMessage::ProtocolError { reason }).await;
+    Err(())
+}
+
+/// Waits for the spectator's `Watch` and subscribes to the game.
+async fn watch_game(
+    websocket: &mut WebSocket,
+    game_pool: &Mutex<GamePool>,
+    accounts: &Mutex<Accounts>,
+    pid: usize,
+    heartbit_interval: Duration,
+) -> Option<mpsc::UnboundedReceiver<SpectatorEvent>> {
+    let mut last_message = Instant::now();
+
+    let (id, reveal_hands) = loop {
+        let message = match websocket_next(websocket, &mut last_message, heartbit_interval).await {
+            Ok(message) => message,
+            Err(()) => return None,
+        };
+        let txt = match message.to_str() {
+            Ok(txt) => txt,
+            Err(()) => continue,
+        };
+        match serde_json::from_str(txt) {
+            Ok(ClientMessage::Watch { game, reveal_hands }) => break (game, reveal_hands),
+            Ok(ClientMessage::Ping) => send_response(websocket, &ServerMessage::Pong).await,
+            _ => send_response(websocket, &ServerMessage::JsonError).await,
+        }
+    };
+
+    let reveal_delay = if reveal_hands {
+        Some(SPECTATOR_REVEAL_DELAY)
+    } else {
+        None
+    };
+    // Accounts are locked before the pool, so they aren't locked together here
+    let players = game_pool
+        .lock()
+        .unwrap()
+        .games
+        .get(&id)
+        .map(|game| game.players.clone());
+    let own_game = players.as_ref().is_some_and(|players| {
+        let accounts = accounts.lock().unwrap();
+        players.iter().any(|&id| accounts.same_person(pid, id))
+    });
+    if own_game {
+        info!("SPECTATOR {} can't watch own game {}", pid, id);
+        send_response(websocket, &ServerMessage::OwnGame).await;
+        return None;
+    }
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let players = match game_pool.lock().unwrap().games.get(&id) {
+        Some(game)
+            if game
+                .requests
+                .send((pid, GameRequest::Watch(tx, reveal_delay)))
+                .is_ok() =>
+        {
+            Some(game.players.clone())
+        }
+        _ => None,
+    };
+    let players = match players {
+        Some(players) => players,
+        None => {
+            send_response(websocket, &ServerMessage::NoSuchGame).await;
+            return None;
+        }
+    };
+
+    let players = {
+        let accounts = accounts.lock().unwrap();
+        players
+            .into_iter()
+            .map(|pid| accounts.nickname(pid))
+            .collect()
+    };
+    send_response(
+        websocket,
+        &ServerMessage::Watching {
+            game: id,
+            players,
+            reveal_delay,
+        },
+    )
+    .await;
+    Some(rx)
+}
+
+async fn spectator_handling(
+    mut websocket: WebSocket,
     game_pool: Arc<Mutex<GamePool>>,
+    accounts: Arc<Mutex<Accounts>>,
     pid: usize,
 ) {
-    let (is_ret, restr_game) = player_init(Arc::clone(&game_pool), pid);
+    let config = Arc::clone(&game_pool.lock().unwrap().config);
+    let heartbit_interval = config.heartbit_interval();
+    // Bots can't watch games, so spectators aren't rate limited
+    let version = match handshake(&mut websocket, heartbit_interval, &mut None).await {
+        Ok(version) => version,
+        Err(()) => {
+            info!("SPECTATOR {} handshake failed", pid);
+            return;
+        }
+    };
+    let nickname = accounts.lock().unwrap().nickname(pid);
+    send_response(
+        &mut websocket,
+        &ServerMessage::Welcome { version, nickname },
+    )
+    .await;
+
+    let mut events = match watch_game(
+        &mut websocket,
+        &game_pool,
+        &accounts,
+        pid,
+        heartbit_interval,
+    )
+    .await
+    {
+        Some(events) => events,
+        None => return,
+    };
+    info!("SPECTATOR {} is watching", pid);
+
+    let mut last_message = Instant::now();
+
+    loop {
+        let message = tokio::select! {
+            event = events.recv() => {
+                match event {
+                    Some(SpectatorEvent::View(view)) => {
+                        let game_over = view.winner.is_some();
+                        send_response(&mut websocket, &spectator_view(view, &accounts)).await;
+                        if game_over {
+                            break;
+                        }
+                    }
+                    Some(SpectatorEvent::Message(from, text)) => {
+                        let from = accounts.lock().unwrap().nickname(from);
+                        send_response(&mut websocket, &ServerMessage::Message { from, text }).await;
+                    }
+                    Some(SpectatorEvent::PlayerLeft(id)) => {
+                        let nickname = accounts.lock().unwrap().nickname(id);
+                        send_response(&mut websocket, &ServerMessage::PlayerLeft { nickname })
+                            .await;
+                    }
+                    None => break,
+                }
+                continue;
+            }
+            message = websocket_next(&mut websocket, &mut last_message, heartbit_interval) => {
+                match message {
+                    Ok(message) => message,
+                    Err(()) => break,
+                }
+            }
+        };
+
+        let txt = match message.to_str() {
+            Ok(txt) => txt,
+            Err(()) => continue,
+        };
+        let response = match serde_json::from_str(txt) {
+            Ok(ClientMessage::Ping) => ServerMessage::Pong,
+            Ok(ClientMessage::SendMessage { .. }) => ServerMessage::Sent { ok: false },
+            Ok(ClientMessage::Exit) => break,
+            _ => ServerMessage::JsonError,
+        };
+        send_response(&mut websocket, &response).await;
+    }
+
+    info!("SPECTATOR {} stopped watching", pid);
+}
+
+async fn websocket_handling(
+    mut websocket: WebSocket,
+    game_pool: Arc<Mutex<GamePool>>,
+    accounts: Arc<Mutex<Accounts>>,
+    pid: usize,
+    bot: bool,
+    queue: GameQueue,
+    language: Language,
+) {
+    let config = Arc::clone(&game_pool.lock().unwrap().config);
+    let heartbit_interval = config.heartbit_interval();
+    // Bots are limited from the handshake to the end of the game
+    let mut rate_limit = if bot && config.bot_rate_limit > 0 {
+        Some(RateLimit::new(config.bot_rate_limit))
+    } else {
+        None
+    };
+    let version = match handshake(&mut websocket, heartbit_interval, &mut rate_limit).await {
+        Ok(version) => version,
+        Err(()) => {
+            info!("PLAYER {} handshake failed", pid);
+            return;
+        }
+    };
+
+    let arena = queue.arena;
+    let (is_ret, restr_game) = player_init(Arc::clone(&game_pool), pid, queue).await;
     if is_ret {
-        websocket
-            .send_text(&serde_json::to_string(&JsonResponse::YouArePlaying).unwrap())
-            .ok();
+        send_response(&mut websocket, &ServerMessage::YouArePlaying).await;
         info!("PLAYER {} is playing from another socket", pid);
         return;
     }
 
-    websocket
-        .send_text(&serde_json::to_string(&JsonResponse::ID(pid)).unwrap())
-        .ok();
+    let nickname = accounts.lock().unwrap().nickname(pid);
+    send_response(
+        &mut websocket,
+        &ServerMessage::Welcome { version, nickname },
+    )
+    .await;
 
-    let mut game = if let Some(game) = restr_game {
+    let PlayerGame { gid, mut client } = if let Some(game) = restr_game {
         game
-    } else if game_pool.lock().unwrap().waiting_players.len() < 2 {
-        websocket = match wait_game(websocket, game_pool.clone(), pid) {
+    } else {
+        websocket = match wait_game(websocket, game_pool.clone(), pid, &mut rate_limit).await {
             Some(websocket) => websocket,
             None => return,
         };
-        if let Some(x) = game_pool.lock().unwrap().players_channels.remove(&pid) {
-            x
-        } else {
-            return;
+        let game = game_pool.lock().unwrap().players_channels.remove(&pid);
+        match game {
+            Some(game) => game,
+            None => return,
         }
+    };
+
+    info!("PLAYER {} is playing GAME {}!", pid, gid);
+    let ws_end_success = match play_game(
+        &mut websocket,
+        &mut client,
+        &game_pool,
+        &accounts,
+        bot,
+        arena,
+        language,
+        &mut rate_limit,
+    )
+    .await
+    {
+        Ok(ws_end_success) => ws_end_success,
+        Err(e) => {
+            warn!("PLAYER {} game stopped: {}", pid, e);
+            true
+        }
+    };
+
+    let websocket = if ws_end_success {
+        Some(websocket)
     } else {
-        let mut game_pool = game_pool.lock().unwrap();
-        game_create(&mut game_pool);
-        game_pool
-            .players_channels
-            .remove(&pid)
-            .unwrap()
+        None
     };
 
-    info!("PLAYER {} is playing!", pid);
-    websocket
-        .send_text(
-            &serde_json::to_string(&JsonResponse::YourCards(
-                game.get_my_cards(),
-                game.get_deck_size(),
-            ))
-            .unwrap(),
-        )
-        .ok();
+    let game = PlayerGame { gid, client };
+    game_exit(game_pool, Some(game), websocket, Some(ws_end_success), pid);
+}
+
+/// Returns `false` if the websocket was closed before the game was over.
+#[allow(clippy::too_many_arguments)]
+async fn play_game(
+    websocket: &mut WebSocket,
+    game: &mut GameChannelClient,
+    game_pool: &Mutex<GamePool>,
+    accounts: &Mutex<Accounts>,
+    bot: bool,
+    arena: bool,
+    language: Language,
+    rate_limit: &mut Option<RateLimit>,
+) -> Result<bool, WorkerExited> {
+    let pid = game.1;
+    let config = Arc::clone(&game_pool.lock().unwrap().config);
+    let heartbit_interval = config.heartbit_interval();
+    // A player coming back to a game on a server shutting down is told at once
+    let mut shutdown = game_pool.lock().unwrap().shutdown.subscribe();
+    shutdown.mark_changed();
+
+    let view = game.get_view().await?;
+    let opponents = {
+        let accounts = accounts.lock().unwrap();
+        let rating = |id| {
+            let game_pool = game_pool.lock().unwrap();
+            if arena {
+                game_pool.arena.lock().unwrap().ratings.get(id).rating
+            } else {
+                game_pool.ratings.lock().unwrap().get(id).rating
+            }
+        };
+        view.seats
+            .iter()
+            .filter(|seat| seat.pid != pid)
+            .map(|seat| OpponentInfo {
+                nickname: accounts.nickname(seat.pid),
+                rating: rating(seat.pid).round() as i64,
+            })
+            .collect()
+    };
+    send_response(websocket, &ServerMessage::Opponents { opponents }).await;
+    send_response(websocket, &ServerMessage::your_cards(&view)).await;
+    send_response(websocket, &game_snapshot(&view, accounts)).await;
 
     let mut your_turn_new = true;
     let mut ws_end_success = false;
 
-    let mut stepping_time: Option<Instant> = {
-        let mut game_pool = game_pool.lock().unwrap();
-        match game_pool.players_time.remove(&pid) {
-            Some(time) => time,
-            None => None,
-        }
-    };
+    if let Some(resp) = your_turn(&view, &mut your_turn_new, bot) {
+        send_response(websocket, &resp).await;
+    }
 
-    let mut websocket = Some(websocket);
-    let mut last_refresh = Instant::now();
+    let mut last_message = Instant::now();
 
     loop {
-        match websocket_next(websocket.unwrap()) {
-            None => {
-                websocket = None;
-                break;
-            }
-            Some((mut ws, message)) => {
-                if last_refresh.elapsed() > REFRESH_DURATION {
-                    match refresh_time(&mut game, &mut stepping_time, &mut your_turn_new, pid) {
-                        Ok(Some(msg)) => {
-                            ws.send_text(&msg).ok();
+        let message = tokio::select! {
+            event = game.next_event() => {
+                let event = event.ok_or(WorkerExited)?;
+                match event {
+                    GameEvent::StepMade(id) if id == pid => {
+                        // The step may have been made by the server when the time ran out
+                        your_turn_new = true;
+                        let view = game.get_view().await?;
+                        send_response(websocket, &game_snapshot(&view, accounts)).await;
+                    }
+                    GameEvent::StepMade(_) | GameEvent::CardsDealt => {
+                        let view = game.get_view().await?;
+                        if let GameEvent::CardsDealt = event {
+                            send_response(websocket, &ServerMessage::your_cards(&view)).await;
+                        } else {
+                            // Cards are dealt within the step, so one snapshot covers both
+                            send_response(websocket, &game_snapshot(&view, accounts)).await;
                         }
-                        Err(()) => {
+                        if let Some(resp) = your_turn(&view, &mut your_turn_new, bot) {
+                            send_response(websocket, &resp).await;
+                        }
+                    }
+                    GameEvent::Message(from, msg) => {
+                        info!("MESSAGE \"{}\" from {} sent to {}", msg, from, pid);
+                        let from = accounts.lock().unwrap().nickname(from);
+                        send_response(websocket, &ServerMessage::Message { from, text: msg }).await;
+                    }
+                    GameEvent::PlayerLeft(id) => {
+                        let nickname = accounts.lock().unwrap().nickname(id);
+                        send_response(websocket, &ServerMessage::PlayerLeft { nickname }).await;
+                        let view = game.get_view().await?;
+                        send_response(websocket, &game_snapshot(&view, accounts)).await;
+                    }
+                    GameEvent::OutOfTime(id, timeouts) => {
+                        info!("PLAYER {} sees {} out of time", pid, id);
+                        let nickname = accounts.lock().unwrap().nickname(id);
+                        send_response(websocket, &ServerMessage::OutOfTime { nickname, timeouts })
+                            .await;
+                        if id == pid && game.get_view().await?.is_kicked() {
                             ws_end_success = true;
-                            websocket = Some(ws);
                             break;
                         }
-                        _ => (),
                     }
-
-                    last_refresh = Instant::now();
+                    GameEvent::GameOver(winner) => {
+                        info!("PLAYER {} game over, winner {:?}", pid, winner);
+                        let view = game.get_view().await?;
+                        send_response(websocket, &game_snapshot(&view, accounts)).await;
+                        ws_end_success = true;
+                        break;
+                    }
                 }
-
-                for msg in game.get_messages() {
-                    info!("MESSAGE \"{}\" sent to {}", msg, pid);
-                    ws.send_text(&serde_json::to_string(&JsonResponse::Message(msg)).unwrap())
-                        .ok();
+                continue;
+            }
+            Ok(()) = shutdown.changed() => {
+                let message = shutdown_message(&game_pool.lock().unwrap());
+                if let Some(message) = message {
+                    info!("PLAYER {} is told the server is shutting down", pid);
+                    send_response(websocket, &message).await;
+                }
+                continue;
+            }
+            message = websocket_next(websocket, &mut last_message, heartbit_interval) => {
+                match message {
+                    Ok(message) => message,
+                    Err(()) => break,
                 }
+            }
+        };
 
-                if let websocket::Message::Text(txt) = message {
-                    if txt != "\"Ping\"" {
-                        info!("PLAYER From {} request {}", pid, txt);
-                    }
+        if let Ok(txt) = message.to_str() {
+            if rate_limited(websocket, rate_limit).await {
+                info!("PLAYER {} is rate limited", pid);
+                continue;
+            }
 
-                    let json_response = match serde_json::from_str(&txt) {
-                        Ok(json_request) => match json_request {
-                            JsonRequest::Ping => JsonResponse::Pong,
-                            JsonRequest::MakeStep(step) => match game.make_step(step) {
-                                Ok(()) => {
-                                    your_turn_new = true;
-                                    stepping_time = None;
-                                    if game.is_me_kicked() {
-                                        ws_end_success = true;
-                                        websocket = Some(ws);
-                                        break;
-                                    } else {
-                                        JsonResponse::YouMadeStep(
-                                            game.get_state_cards(),
-                                            game.get_my_cards(),
-                                            game.get_deck_size(),
-                                            game.get_another_number_of_cards(
-                                                game.get_stepping_player(),
-                                            ),
-                                        )
-                                    }
-                                }
-                                Err(e) => JsonResponse::StepError(e),
-                            },
-                            JsonRequest::SendMessage(msg) => {
-                                if msg.len() <= MAX_MESSAGE_LENGTH {
-                                    game.send_message(msg);
-                                    JsonResponse::Sent(Ok(()))
+            let json_response = match serde_json::from_str(txt) {
+                Ok(json_request) => match json_request {
+                    ClientMessage::Ping => ServerMessage::Pong,
+                    ClientMessage::Hello { .. } | ClientMessage::Watch { .. } => {
+                        ServerMessage::JsonError
+                    }
+                    ClientMessage::MakeStep { step } => {
+                        let outcome = game.make_step(step.clone().into()).await?;
+                        match outcome.result {
+                            Ok(()) => {
+                                your_turn_new = true;
+                                if outcome.view.is_kicked() {
+                                    ws_end_success = true;
+                                    break;
                                 } else {
-                                    JsonResponse::Sent(Err(()))
+                                    ServerMessage::you_made_step(&outcome.view)
                                 }
                             }
-                            JsonRequest::Exit => {
-                                game.kick_me();
-                                ws_end_success = true;
-                                JsonResponse::GameLoser
-                            }
-                        },
-                        Err(_) => JsonResponse::JsonError,
-                    };
-
-                    let resp = serde_json::to_string(&json_response).unwrap();
-
-                    match &json_response {
-                        JsonResponse::Pong => (),
-                        _ => {
-                            info!("PLAYER Response {} to {}", resp, pid);
+                            Err(e) => step_error(e, step, &outcome.view, accounts, language),
                         }
                     }
-
-                    ws.send_text(&resp).unwrap();
-
-                    if ws_end_success {
-                        websocket = Some(ws);
-                        break;
+                    ClientMessage::SendMessage { text } => {
+                        if text.len() <= config.max_message_length {
+                            game.send_message(text)?;
+                            ServerMessage::Sent { ok: true }
+                        } else {
+                            ServerMessage::Sent { ok: false }
+                        }
                     }
-                } else {
-                    warn!("PLAYER Unknown message from a websocket {}", pid);
+                    ClientMessage::Exit => {
+                        game.kick_me()?;
+                        ws_end_success = true;
+                        ServerMessage::GameLoser
+                    }
+                },
+                Err(_) => ServerMessage::JsonError,
+            };
+
+            match &json_response {
+                ServerMessage::Pong => (),
+                _ => {
+                    info!("PLAYER From {} request {}", pid, txt);
+                    info!(
+                        "PLAYER Response {} to {}",
+                        serde_json::to_string(&json_response).unwrap(),
+                        pid
+                    );
                 }
-                websocket = Some(ws);
             }
-        }
-    }
 
-    if !ws_end_success {
-        game_pool
-            .lock()
-            .unwrap()
-            .players_time
-            .insert(pid, stepping_time);
+            send_response(websocket, &json_response).await;
+
+            if ws_end_success {
+                break;
+            }
+        } else {
+            warn!("PLAYER Unknown message from a websocket {}", pid);
+        }
     }
 
-    game_exit(game_pool, Some(game), websocket, Some(ws_end_success), pid);
+    Ok(ws_end_success)
 }
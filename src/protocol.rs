@@ -0,0 +1,429 @@
+//! Websocket protocol between the server and game clients, its JSON Schema is
+//! served at `/api/protocol/schema`.
+//!
+//! Adding a message type or an optional field keeps the version, so clients
+//! must ignore unknown ones. Any other change increases `PROTOCOL_VERSION`.
+
+use std::collections::HashSet;
+
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{RootSchema, Schema};
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+
+use crate::card::Card;
+use crate::game::{legal_steps, PlayerView, State, Step, StepError, PID};
+
+mod test;
+
+pub const PROTOCOL_VERSION: u32 = 2;
+pub const MIN_PROTOCOL_VERSION: u32 = 2;
+
+impl JsonSchema for Card {
+    fn schema_name() -> String {
+        "Card".to_string()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        serde_json::from_value(serde_json::json!({
+            "description": "A card as a [rank, suit] pair",
+            "type": "array",
+            "items": [
+                {
+                    "type": "string",
+                    "enum": ["2", "3", "4", "5", "6", "7", "8", "9", "10", "J", "Q", "K", "A"]
+                },
+                {
+                    "type": "string",
+                    "enum": ["♠", "♣", "♦", "♥"]
+                }
+            ],
+            "minItems": 2,
+            "maxItems": 2
+        }))
+        .unwrap()
+    }
+}
+
+/// Cards on the board when the player has to beat a combination.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct BoardView {
+    /// The combination to beat.
+    pub comb: HashSet<Card>,
+    /// The kind of the combination, e.g. `Pair` or `FullHouse`.
+    pub comb_name: String,
+    /// All cards on the board including the combination.
+    pub cards: HashSet<Card>,
+}
+
+impl BoardView {
+    /// Returns `None` in the passive state.
+    pub fn from_state(state: &State) -> Option<BoardView> {
+        match state {
+            State::Passive => None,
+            State::Active(board) => Some(BoardView {
+                comb: board.comb.cards.clone(),
+                comb_name: board.comb.name().to_string(),
+                cards: board.cards.clone(),
+            }),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct OpponentInfo {
+    pub nickname: String,
+    pub rating: i64,
+}
+
+/// A player at the table as seen by other players and spectators.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct SeatView {
+    pub seat: usize,
+    pub nickname: String,
+    pub cards: usize,
+    /// `false` once the player has left the game or got rid of all cards.
+    pub playing: bool,
+    /// Seconds left on the player's game clock if the game has one.
+    pub clock: Option<u64>,
+}
+
+/// A player's hand shown to spectators with a delay.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct RevealedHand {
+    pub nickname: String,
+    pub cards: HashSet<Card>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind")]
+pub enum GameStatus {
+    Playing,
+    Finished { winner: Option<String> },
+}
+
+impl GameStatus {
+    /// `nickname` names the players.
+    pub fn new(winner: Option<PID>, nickname: impl Fn(PID) -> String) -> GameStatus {
+        match winner {
+            None => GameStatus::Playing,
+            Some(winner) => GameStatus::Finished {
+                winner: Some(nickname(winner)),
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub enum StepKind {
+    GetCard,
+    GiveComb,
+    TransComb,
+    GetComb,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind")]
+pub enum StepMessage {
+    GetCard,
+    GiveComb { cards: HashSet<Card> },
+    TransComb { cards: HashSet<Card> },
+    GetComb,
+}
+
+impl From<Step> for StepMessage {
+    fn from(step: Step) -> StepMessage {
+        match step {
+            Step::GetCard => StepMessage::GetCard,
+            Step::GiveComb(cards) => StepMessage::GiveComb { cards },
+            Step::TransComb(cards) => StepMessage::TransComb { cards },
+            Step::GetComb => StepMessage::GetComb,
+        }
+    }
+}
+
+impl From<StepMessage> for Step {
+    fn from(step: StepMessage) -> Step {
+        match step {
+            StepMessage::GetCard => Step::GetCard,
+            StepMessage::GiveComb { cards } => Step::GiveComb(cards),
+            StepMessage::TransComb { cards } => Step::TransComb(cards),
+            StepMessage::GetComb => Step::GetComb,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub enum StepErrorCode {
+    InvalidPID,
+    InvalidStepType,
+    InvalidCards,
+    InvalidComb,
+    WeakComb,
+}
+
+impl From<StepError> for StepErrorCode {
+    fn from(error: StepError) -> StepErrorCode {
+        match error {
+            StepError::InvalidPID => StepErrorCode::InvalidPID,
+            StepError::InvalidStepType => StepErrorCode::InvalidStepType,
+            StepError::InvalidCards => StepErrorCode::InvalidCards,
+            StepError::InvalidComb => StepErrorCode::InvalidComb,
+            StepError::WeakComb => StepErrorCode::WeakComb,
+        }
+    }
+}
+
+/// Details helping to understand why a step was rejected.
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind")]
+pub enum StepErrorContext {
+    /// It is another player's turn.
+    NotYourTurn {
+        stepping: String,
+    },
+    AllowedSteps {
+        steps: Vec<StepKind>,
+    },
+    /// Cards of the step which are neither in the hand nor on the board.
+    MissingCards {
+        cards: HashSet<Card>,
+    },
+    /// The combination which must be beaten.
+    CombToBeat {
+        board: BoardView,
+    },
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub enum ProtocolErrorReason {
+    /// The first message wasn't `Hello`.
+    HandshakeExpected,
+    UnsupportedVersion {
+        min: u32,
+        max: u32,
+    },
+}
+
+/// Messages sent by a client.
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type")]
+pub enum ClientMessage {
+    Hello {
+        version: u32,
+    },
+    Ping,
+    MakeStep {
+        step: StepMessage,
+    },
+    SendMessage {
+        text: String,
+    },
+    /// Starts watching a game, allowed only on `/ws/watch`.
+    Watch {
+        game: usize,
+        reveal_hands: bool,
+    },
+    Exit,
+}
+
+/// Messages sent by the server.
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type")]
+pub enum ServerMessage {
+    Welcome {
+        version: u32,
+        nickname: String,
+    },
+    ProtocolError {
+        reason: ProtocolErrorReason,
+    },
+    Pong,
+    /// The player already plays from another connection.
+    YouArePlaying,
+    Opponents {
+        opponents: Vec<OpponentInfo>,
+    },
+    YourCards {
+        cards: HashSet<Card>,
+        deck_size: usize,
+    },
+    /// The whole game as seen by the player, sent after every change.
+    GameSnapshot {
+        cards: HashSet<Card>,
+        board: Option<BoardView>,
+        deck_size: usize,
+        /// The player's own seat.
+        seat: usize,
+        opponents: Vec<SeatView>,
+        turn_seat: usize,
+        your_turn: bool,
+        /// Seconds left for the current step.
+        time_left: u64,
+        /// Seconds left on the player's game clock if the game has one.
+        clock: Option<u64>,
+        status: GameStatus,
+    },
+    Watching {
+        game: usize,
+        players: Vec<String>,
+        /// Steps after which hands are revealed, `null` if they are hidden.
+        reveal_delay: Option<usize>,
+    },
+    /// The game has finished or never existed.
+    NoSuchGame,
+    /// The spectator plays in the game or owns a bot playing in it.
+    OwnGame,
+    /// The game as seen by a spectator.
+    SpectatorView {
+        board: Option<BoardView>,
+        deck_size: usize,
+        players: Vec<SeatView>,
+        turn_seat: usize,
+        steps: usize,
+        status: GameStatus,
+        hands: Option<Vec<RevealedHand>>,
+    },
+    /// The turn has passed to the player.
+    YourTurn {
+        board: Option<BoardView>,
+        cards: HashSet<Card>,
+        deck_size: usize,
+        opponent_cards: usize,
+        /// Seconds left to make a step.
+        time_left: u64,
+        /// Sent to bots only, not with a hand too big to list the steps.
+        legal_steps: Option<Vec<StepMessage>>,
+    },
+    YouMadeStep {
+        board: Option<BoardView>,
+        cards: HashSet<Card>,
+        deck_size: usize,
+        opponent_cards: usize,
+    },
+    StepError {
+        error: StepErrorCode,
+        /// The error description in the player's language.
+        message: String,
+        context: Option<StepErrorContext>,
+    },
+    Message {
+        from: String,
+        text: String,
+    },
+    PlayerLeft {
+        nickname: String,
+    },
+    /// The server made a step for the player or the player left the game.
+    OutOfTime {
+        nickname: String,
+        /// How many turns in a row the player has run out of time.
+        timeouts: u32,
+    },
+    /// Whether the chat message was accepted.
+    Sent {
+        ok: bool,
+    },
+    /// The request couldn't be parsed.
+    JsonError,
+    /// The bot sends messages too often, the message was dropped.
+    RateLimited,
+    /// The game is stopped if it isn't over in `wait` seconds.
+    ServerShutdown {
+        wait: u64,
+    },
+    GameWinner,
+    GameLoser,
+}
+
+/// Messages to a player are built from the player's view only.
+impl ServerMessage {
+    pub fn your_cards(view: &PlayerView) -> ServerMessage {
+        ServerMessage::YourCards {
+            cards: view.cards.clone(),
+            deck_size: view.deck_size,
+        }
+    }
+
+    /// The `YourTurn` message, with the legal steps if they are asked for.
+    pub fn your_turn(view: &PlayerView, with_legal_steps: bool) -> ServerMessage {
+        ServerMessage::YourTurn {
+            board: BoardView::from_state(&view.state),
+            cards: view.cards.clone(),
+            deck_size: view.deck_size,
+            opponent_cards: view.next_seat_cards(view.pid),
+            time_left: view.time_left.map_or(0, |time| time.as_secs()),
+            legal_steps: if with_legal_steps {
+                legal_steps(&view.cards, &view.state, view.deck_size)
+                    .map(|steps| steps.into_iter().map(StepMessage::from).collect())
+            } else {
+                None
+            },
+        }
+    }
+
+    /// The `YouMadeStep` message, the opponent is the player who steps next.
+    pub fn you_made_step(view: &PlayerView) -> ServerMessage {
+        ServerMessage::YouMadeStep {
+            board: BoardView::from_state(&view.state),
+            cards: view.cards.clone(),
+            deck_size: view.deck_size,
+            opponent_cards: view.cards_of(view.stepping_player),
+        }
+    }
+
+    /// The `GameSnapshot` message, `nickname` names the players.
+    pub fn game_snapshot(view: &PlayerView, nickname: impl Fn(PID) -> String) -> ServerMessage {
+        let pid = view.pid;
+        let seat_of = |id| {
+            view.seats
+                .iter()
+                .find(|seat| seat.pid == id)
+                .map_or(0, |seat| seat.seat)
+        };
+
+        ServerMessage::GameSnapshot {
+            board: BoardView::from_state(&view.state),
+            deck_size: view.deck_size,
+            seat: seat_of(pid),
+            opponents: view
+                .seats
+                .iter()
+                .filter(|seat| seat.pid != pid)
+                .map(|seat| SeatView {
+                    seat: seat.seat,
+                    nickname: nickname(seat.pid),
+                    cards: seat.cards,
+                    playing: seat.playing,
+                    clock: seat.clock.map(|clock| clock.as_secs()),
+                })
+                .collect(),
+            turn_seat: seat_of(view.stepping_player),
+            your_turn: view.is_my_turn(),
+            time_left: view.time_left.map_or(0, |time| time.as_secs()),
+            clock: view
+                .seats
+                .iter()
+                .find(|seat| seat.pid == pid)
+                .and_then(|seat| seat.clock)
+                .map(|clock| clock.as_secs()),
+            status: GameStatus::new(view.winner, &nickname),
+            cards: view.cards.clone(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ProtocolSchema {
+    pub version: u32,
+    pub client: RootSchema,
+    pub server: RootSchema,
+}
+
+pub fn protocol_schema() -> ProtocolSchema {
+    ProtocolSchema {
+        version: PROTOCOL_VERSION,
+        client: schema_for!(ClientMessage),
+        server: schema_for!(ServerMessage),
+    }
+}
@@ -0,0 +1,78 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::card::*;
+
+    use crate::protocol::*;
+
+    #[test]
+    fn protocol_test_client_messages() {
+        match serde_json::from_str(r#"{"type": "Hello", "version": 2}"#).unwrap() {
+            ClientMessage::Hello { version } => assert_eq!(version, PROTOCOL_VERSION),
+            _ => panic!(),
+        }
+
+        match serde_json::from_str(
+            r#"{"type": "MakeStep", "step": {"kind": "GiveComb", "cards": [["10", "♥"]]}}"#,
+        )
+        .unwrap()
+        {
+            ClientMessage::MakeStep {
+                step: StepMessage::GiveComb { cards },
+            } => assert!(cards.contains(&Card {
+                rank: CardRank::Ten,
+                suit: CardSuit::Hearts
+            })),
+            _ => panic!(),
+        }
+
+        assert!(serde_json::from_str::<ClientMessage>(r#"{"MakeStep": "GetCard"}"#).is_err());
+    }
+
+    #[test]
+    fn protocol_test_server_messages() {
+        assert_eq!(
+            serde_json::to_value(&ServerMessage::Sent { ok: true }).unwrap(),
+            serde_json::json!({"type": "Sent", "ok": true})
+        );
+        assert_eq!(
+            serde_json::to_value(&ServerMessage::GameWinner).unwrap(),
+            serde_json::json!({"type": "GameWinner"})
+        );
+        assert_eq!(
+            serde_json::to_value(&ServerMessage::ServerShutdown { wait: 60 }).unwrap(),
+            serde_json::json!({"type": "ServerShutdown", "wait": 60})
+        );
+        assert_eq!(
+            serde_json::to_value(&GameStatus::Finished {
+                winner: Some("player".to_string())
+            })
+            .unwrap(),
+            serde_json::json!({"kind": "Finished", "winner": "player"})
+        );
+        assert_eq!(
+            serde_json::to_value(&ServerMessage::StepError {
+                error: StepErrorCode::InvalidPID,
+                message: "You can't make a step now".to_string(),
+                context: Some(StepErrorContext::NotYourTurn {
+                    stepping: "player".to_string()
+                }),
+            })
+            .unwrap(),
+            serde_json::json!({
+                "type": "StepError",
+                "error": "InvalidPID",
+                "message": "You can't make a step now",
+                "context": {"kind": "NotYourTurn", "stepping": "player"}
+            })
+        );
+    }
+
+    #[test]
+    fn protocol_test_schema() {
+        let schema = serde_json::to_value(protocol_schema()).unwrap();
+        assert_eq!(schema["version"], PROTOCOL_VERSION);
+        assert!(schema["client"]["definitions"]["Card"].is_object());
+        assert!(schema["server"]["definitions"]["BoardView"].is_object());
+    }
+}
@@ -0,0 +1,43 @@
+//! Limits how often a client may send messages.
+//!
+//! A client has a budget of messages which refills at the allowed rate, so
+//! short bursts of up to a second's worth of messages pass.
+
+use std::time::Instant;
+
+mod test;
+
+pub struct RateLimit {
+    per_second: f64,
+    budget: f64,
+    refilled: Instant,
+}
+
+impl RateLimit {
+    pub fn new(per_second: u32) -> RateLimit {
+        RateLimit {
+            per_second: per_second as f64,
+            budget: per_second as f64,
+            refilled: Instant::now(),
+        }
+    }
+
+    /// Spends a message of the budget at the moment, returns `false` if the
+    /// client is sending too often.
+    pub fn check_at(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.refilled).as_secs_f64();
+        self.budget = (self.budget + elapsed * self.per_second).min(self.per_second);
+        self.refilled = now;
+
+        if self.budget >= 1.0 {
+            self.budget -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn check(&mut self) -> bool {
+        self.check_at(Instant::now())
+    }
+}
@@ -0,0 +1,24 @@
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use crate::rate_limit::*;
+
+    #[test]
+    fn rate_limit_test() {
+        let start = Instant::now();
+        let mut limit = RateLimit::new(3);
+
+        assert!((0..3).all(|_| limit.check_at(start)));
+        assert!(!limit.check_at(start));
+        assert!(!limit.check_at(start + Duration::from_millis(300)));
+        assert!(limit.check_at(start + Duration::from_millis(340)));
+        assert!(!limit.check_at(start + Duration::from_millis(400)));
+
+        // The budget doesn't grow beyond a second's worth of messages
+        let later = start + Duration::from_secs(10);
+        assert!((0..3).all(|_| limit.check_at(later)));
+        assert!(!limit.check_at(later));
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::fs::File;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{GameResult, PID};
+
+mod test;
+
+const RATINGS_FILE: &str = "ratings.json";
+const INITIAL_RATING: f64 = 1500.0;
+const K_FACTOR: f64 = 32.0;
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Rating {
+    pub rating: f64,
+    pub games: usize,
+    pub wins: usize,
+}
+
+impl Default for Rating {
+    fn default() -> Rating {
+        Rating {
+            rating: INITIAL_RATING,
+            games: 0,
+            wins: 0,
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Ratings {
+    ratings: HashMap<PID, Rating>,
+}
+
+fn expected_score(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+fn elo_update(winner: f64, loser: f64) -> (f64, f64) {
+    let delta = K_FACTOR * (1.0 - expected_score(winner, loser));
+    (winner + delta, loser - delta)
+}
+
+impl Ratings {
+    pub fn load() -> Ratings {
+        let ratings = match File::open(RATINGS_FILE) {
+            Ok(file) => match serde_json::from_reader::<_, Ratings>(file) {
+                Ok(ratings) => ratings,
+                Err(e) => {
+                    warn!("RATINGS can't parse {}: {}", RATINGS_FILE, e);
+                    Ratings::default()
+                }
+            },
+            Err(_) => Ratings::default(),
+        };
+        info!("RATINGS {} loaded", ratings.ratings.len());
+        ratings
+    }
+
+    fn save(&self) {
+        match File::create(RATINGS_FILE) {
+            Ok(file) => {
+                if let Err(e) = serde_json::to_writer(file, self) {
+                    warn!("RATINGS can't save {}: {}", RATINGS_FILE, e);
+                }
+            }
+            Err(e) => warn!("RATINGS can't create {}: {}", RATINGS_FILE, e),
+        }
+    }
+
+    pub fn get(&self, pid: PID) -> Rating {
+        self.ratings.get(&pid).copied().unwrap_or_default()
+    }
+
+    /// The winner beats every other player, a game without a winner changes
+    /// nothing.
+    pub(crate) fn update(&mut self, result: &GameResult) -> bool {
+        let winner = match result.winner {
+            Some(winner) => winner,
+            None => return false,
+        };
+
+        for &loser in result.players.iter().filter(|&&pid| pid != winner) {
+            let (w, l) = (self.get(winner), self.get(loser));
+            let (w_rating, l_rating) = elo_update(w.rating, l.rating);
+            self.ratings.insert(
+                winner,
+                Rating {
+                    rating: w_rating,
+                    games: w.games + 1,
+                    wins: w.wins + 1,
+                },
+            );
+            self.ratings.insert(
+                loser,
+                Rating {
+                    rating: l_rating,
+                    games: l.games + 1,
+                    wins: l.wins,
+                },
+            );
+            info!(
+                "RATING {} {:.0} -> {:.0}, {} {:.0} -> {:.0}",
+                winner, w.rating, w_rating, loser, l.rating, l_rating
+            );
+        }
+        true
+    }
+
+    pub fn record(&mut self, result: &GameResult) {
+        if self.update(result) {
+            self.save();
+        }
+    }
+
+    pub fn leaderboard(&self) -> Vec<(PID, Rating)> {
+        let mut leaderboard = self
+            .ratings
+            .iter()
+            .map(|(&pid, &rating)| (pid, rating))
+            .collect::<Vec<_>>();
+        leaderboard.sort_by(|a, b| b.1.rating.partial_cmp(&a.1.rating).unwrap());
+        leaderboard
+    }
+}
@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::game::GameResult;
+
+    use crate::rating::*;
+
+    #[test]
+    fn rating_test_equal_players() {
+        let (w, l) = elo_update(INITIAL_RATING, INITIAL_RATING);
+        assert!((w - (INITIAL_RATING + K_FACTOR / 2.0)).abs() < 1e-9);
+        assert!((l - (INITIAL_RATING - K_FACTOR / 2.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rating_test_upset() {
+        let (strong, weak) = (1800.0, 1400.0);
+        let (w_expected, l_expected) = elo_update(strong, weak);
+        let (w_upset, l_upset) = elo_update(weak, strong);
+        assert!(w_upset - weak > w_expected - strong);
+        assert!(strong - l_upset > weak - l_expected);
+    }
+
+    #[test]
+    fn rating_test_update() {
+        let mut ratings = Ratings::default();
+        assert!(!ratings.update(&GameResult {
+            players: vec![1, 2],
+            winner: None,
+            ..GameResult::default()
+        }));
+        assert_eq!(ratings.get(1).games, 0);
+
+        assert!(ratings.update(&GameResult {
+            players: vec![1, 2, 3],
+            winner: Some(2),
+            ..GameResult::default()
+        }));
+        assert_eq!(ratings.get(2).games, 2);
+        assert_eq!(ratings.get(2).wins, 2);
+        assert_eq!(ratings.get(1).wins, 0);
+        assert_eq!(ratings.leaderboard()[0].0, 2);
+        assert!(ratings.get(1).rating < INITIAL_RATING);
+        assert!(ratings.get(3).rating < INITIAL_RATING);
+    }
+}
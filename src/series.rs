@@ -0,0 +1,121 @@
+//! Rematches of the same players. The series ends when one of its players
+//! starts a game with somebody else.
+
+use std::collections::HashMap;
+
+use crate::game::PID;
+
+mod test;
+
+#[derive(Clone)]
+pub struct Series {
+    pub players: Vec<PID>,
+    /// The room the games are played in, `None` is the default room.
+    pub room: Option<String>,
+    pub wins: HashMap<PID, usize>,
+    pub games: usize,
+    game: Option<usize>,
+    first_player: Option<PID>,
+}
+
+impl Series {
+    /// Whether the last game is over.
+    pub fn is_open(&self) -> bool {
+        self.game.is_none()
+    }
+
+    /// The first step passes to the next player every game.
+    pub fn next_first_player(&self) -> Option<PID> {
+        let first_player = self.first_player?;
+        let pos = self.players.iter().position(|&pid| pid == first_player)?;
+        Some(self.players[(pos + 1) % self.players.len()])
+    }
+}
+
+#[derive(Default)]
+pub struct SeriesPool {
+    series: HashMap<usize, Series>,
+    of_player: HashMap<PID, usize>,
+    counter: usize,
+}
+
+impl SeriesPool {
+    /// Earlier series of the players end.
+    pub fn start(&mut self, players: &[PID], room: Option<String>, game: usize) -> usize {
+        self.leave(players);
+
+        self.counter += 1;
+        let id = self.counter;
+        self.series.insert(
+            id,
+            Series {
+                players: players.to_vec(),
+                room,
+                wins: HashMap::new(),
+                games: 0,
+                game: Some(game),
+                first_player: None,
+            },
+        );
+        for &pid in players {
+            self.of_player.insert(pid, id);
+        }
+        id
+    }
+
+    /// E.g. when they start a game outside of any series.
+    pub fn leave(&mut self, players: &[PID]) {
+        for pid in players {
+            if let Some(id) = self.of_player.get(pid).copied() {
+                self.end(id);
+            }
+        }
+    }
+
+    fn end(&mut self, id: usize) {
+        if let Some(series) = self.series.remove(&id) {
+            for pid in series.players {
+                if self.of_player.get(&pid) == Some(&id) {
+                    self.of_player.remove(&pid);
+                }
+            }
+        }
+    }
+
+    pub fn rematch(&mut self, id: usize, game: usize) -> Option<&Series> {
+        let series = self.series.get_mut(&id).filter(|series| series.is_open())?;
+        series.game = Some(game);
+        Some(series)
+    }
+
+    /// Returns `false` if the game isn't of a series or was already counted.
+    pub fn finish(&mut self, game: usize, first_player: PID, winner: Option<PID>) -> bool {
+        let series = match self
+            .series
+            .values_mut()
+            .find(|series| series.game == Some(game))
+        {
+            Some(series) => series,
+            None => return false,
+        };
+
+        series.game = None;
+        series.games += 1;
+        series.first_player = Some(first_player);
+        if let Some(winner) = winner {
+            *series.wins.entry(winner).or_insert(0) += 1;
+        }
+        true
+    }
+
+    pub fn open(&self, id: usize, pid: PID) -> Option<&Series> {
+        self.series
+            .get(&id)
+            .filter(|series| series.is_open() && series.players.contains(&pid))
+    }
+
+    pub fn of_player(&self, pid: PID) -> Option<(usize, &Series)> {
+        let id = *self.of_player.get(&pid)?;
+        Some((id, self.series.get(&id)?))
+    }
+}
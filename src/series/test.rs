@@ -0,0 +1,45 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::series::*;
+
+    #[test]
+    fn series_test_rematch() {
+        let mut pool = SeriesPool::default();
+        let id = pool.start(&[1, 2], Some("blitz".to_string()), 10);
+
+        assert!(pool.open(id, 1).is_none());
+        assert!(pool.rematch(id, 11).is_none());
+
+        assert!(pool.finish(10, 2, Some(1)));
+        assert!(!pool.finish(10, 2, Some(1)));
+        assert!(pool.open(id, 3).is_none());
+
+        let series = pool.open(id, 1).unwrap();
+        assert_eq!(series.room.as_deref(), Some("blitz"));
+        assert_eq!(series.games, 1);
+        assert_eq!(series.wins.get(&1), Some(&1));
+        assert_eq!(series.next_first_player(), Some(1));
+
+        assert_eq!(pool.rematch(id, 11).unwrap().next_first_player(), Some(1));
+        assert!(pool.finish(11, 1, Some(2)));
+        let (_, series) = pool.of_player(2).unwrap();
+        assert_eq!(series.games, 2);
+        assert_eq!(series.wins.get(&2), Some(&1));
+        assert_eq!(series.next_first_player(), Some(2));
+    }
+
+    #[test]
+    fn series_test_end() {
+        let mut pool = SeriesPool::default();
+        let first = pool.start(&[1, 2], None, 1);
+        pool.finish(1, 1, None);
+        assert_eq!(pool.of_player(1).unwrap().1.wins.len(), 0);
+
+        let second = pool.start(&[1, 3], None, 2);
+        assert!(pool.open(first, 2).is_none());
+        assert!(pool.of_player(2).is_none());
+        assert_eq!(pool.of_player(1).unwrap().0, second);
+        assert_eq!(pool.of_player(3).unwrap().0, second);
+    }
+}
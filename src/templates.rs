@@ -0,0 +1,48 @@
+//! Server-side rendered pages.
+//!
+//! Templates from the `templates` directory are embedded into the binary (see
+//! `assets`) and compiled once at startup.
+//! Every template gets `lang` and the texts of the player's language as `t`
+//! in addition to its route's own context. HTML templates are autoescaped.
+
+use tera::{Context, Tera};
+
+use crate::assets::Assets;
+use crate::i18n::{Language, LANGUAGES};
+
+mod test;
+
+pub struct Templates {
+    tera: Tera,
+    texts: Vec<(Language, serde_json::Value)>,
+}
+
+impl Templates {
+    pub fn load(assets: &Assets) -> Result<Templates, tera::Error> {
+        let mut tera = Tera::default();
+        tera.autoescape_on(vec![".html"]);
+        tera.add_raw_templates(assets.templates())?;
+        info!("TEMPLATES {} loaded", tera.get_template_names().count());
+
+        Ok(Templates {
+            tera,
+            texts: LANGUAGES
+                .iter()
+                .map(|&language| (language, language.texts()))
+                .collect(),
+        })
+    }
+
+    pub fn render(
+        &self,
+        name: &str,
+        language: Language,
+        mut context: Context,
+    ) -> Result<String, tera::Error> {
+        context.insert("lang", language.code());
+        if let Some((_, texts)) = self.texts.iter().find(|(l, _)| *l == language) {
+            context.insert("t", texts);
+        }
+        self.tera.render(name, &context)
+    }
+}
@@ -0,0 +1,85 @@
+#[cfg(test)]
+mod tests {
+
+    use tera::Context;
+
+    use crate::assets::*;
+    use crate::game::TimeControl;
+    use crate::i18n::*;
+
+    use crate::templates::*;
+
+    #[test]
+    fn templates_test_render_all() {
+        let templates = Templates::load(&Assets::load(None)).unwrap();
+
+        let mut context = Context::new();
+        context.insert("host", "localhost:8000");
+        context.insert("heartbit_interval", &15);
+        context.insert("hand_size", "5");
+        context.insert("room", "blitz");
+        context.insert("rematch", &Some(1));
+        context.insert(
+            "series",
+            &serde_json::json!({"id": 1, "games": 2, "open": true, "score": [
+                {"nickname": "alice", "wins": 1},
+                {"nickname": "bob", "wins": 0},
+            ]}),
+        );
+        context.insert("time_control", &TimeControl::default());
+        context.insert(
+            "rooms",
+            &serde_json::json!([{"name": "blitz", "time_control": TimeControl::default()}]),
+        );
+        context.insert(
+            "tournaments",
+            &serde_json::json!([{"id": 1, "name": "Cup", "format": "round_robin",
+                "room": null, "best_of": 3, "stage": "running", "players": 4}]),
+        );
+        context.insert(
+            "tournament",
+            &serde_json::json!({"id": 1, "name": "Cup", "organizer": "alice",
+                "format": "single_elimination", "room": "blitz", "best_of": 3,
+                "stage": "registration", "players": ["alice", "bob"], "joined": false,
+                "organizing": true}),
+        );
+        context.insert("max_best_of", &9);
+        context.insert("max_name_length", &64);
+        context.insert("bots", &["robot"]);
+        context.insert(
+            "token",
+            &serde_json::json!({"nickname": "robot", "token": "0123abcd"}),
+        );
+        context.insert("all_games", &2);
+        context.insert("now_games", &1);
+
+        for name in templates.tera.get_template_names() {
+            for &language in LANGUAGES.iter() {
+                if let Err(e) = templates.render(name, language, context.clone()) {
+                    panic!("{} in {:?}: {:?}", name, language, e);
+                }
+            }
+        }
+
+        context.insert("hand_size", "7");
+        let about = templates
+            .render("about.html", Language::En, context)
+            .unwrap();
+        assert!(about.contains("dealt 7 cards"));
+        assert!(about.contains("filled up to 7 cards"));
+    }
+
+    #[test]
+    fn templates_test_escape() {
+        let templates = Templates::load(&Assets::load(None)).unwrap();
+        let mut context = Context::new();
+        context.insert("all_games", "<b>");
+        context.insert("now_games", &1);
+
+        let page = templates
+            .render("stat.html", Language::En, context)
+            .unwrap();
+        assert!(page.contains("<html lang=\"en\">"));
+        assert!(page.contains("Games started: &lt;b&gt;"));
+    }
+}
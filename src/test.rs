@@ -0,0 +1,77 @@
+#[cfg(test)]
+mod tests {
+
+    use std::time::{Duration, Instant};
+
+    use crate::*;
+
+    fn game_pool() -> GamePool {
+        GamePool {
+            players: HashSet::new(),
+            players_channels: HashMap::new(),
+            waiting_players: HashMap::new(),
+            on_delete: HashMap::new(),
+            games: HashMap::new(),
+            ratings: Arc::default(),
+            history: Arc::default(),
+            arena: Arc::default(),
+            series: SeriesPool::default(),
+            tournaments: Tournaments::default(),
+            config: Arc::new(Config::default()),
+            counter: 0,
+            playing: 0,
+            shutdown: watch::channel(None).0,
+            drain: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn main_test_socket_lost_while_paired() {
+        // Finished games are recorded to the current directory
+        let dir = std::env::temp_dir().join(format!("poker-durak-main-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::env::set_current_dir(&dir).unwrap();
+
+        let game_pool = Arc::new(Mutex::new(game_pool()));
+        let queue = GameQueue {
+            room: None,
+            rematch: None,
+            tournament: None,
+            arena: false,
+        };
+        {
+            let mut game_pool = game_pool.lock().unwrap();
+            for pid in [1, 2] {
+                let waiting = WaitingPlayer {
+                    since: Instant::now(),
+                    queue: queue.clone(),
+                    wake: Arc::default(),
+                };
+                game_pool.waiting_players.insert(pid, waiting);
+            }
+            // Player 2 pairs them while the websocket of player 1 is failing
+            let rules = game_pool.config.room_rules(None).unwrap();
+            game_create(&mut game_pool, vec![2, 1], rules, queue);
+        }
+        stop_waiting(Arc::clone(&game_pool), 1);
+
+        let game = game_pool.lock().unwrap().players_channels.remove(&2);
+        game_exit(Arc::clone(&game_pool), game, None, Some(true), 2);
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            {
+                let game_pool = game_pool.lock().unwrap();
+                if game_pool.games.is_empty() {
+                    assert_eq!(game_pool.playing, 0);
+                    assert!(game_pool.players.is_empty());
+                    assert!(game_pool.players_channels.is_empty());
+                    break;
+                }
+            }
+            assert!(Instant::now() < deadline, "the game is left running");
+            sleep(Duration::from_millis(10)).await;
+        }
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,465 @@
+//! Round-robin and single-elimination tournaments of best-of-N matches. A game
+//! without a winner is played again.
+
+use std::collections::HashMap;
+use std::fs::File;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::PID;
+use crate::i18n::{Localize, DEFAULT_LANGUAGE};
+
+mod test;
+
+const TOURNAMENTS_FILE: &str = "tournaments.json";
+pub const MAX_BEST_OF: usize = 9;
+pub const MAX_NAME_LENGTH: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    RoundRobin,
+    SingleElimination,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "round_robin" => Some(Format::RoundRobin),
+            "single_elimination" => Some(Format::SingleElimination),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Registration,
+    Running,
+    Finished,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TournamentError {
+    InvalidName,
+    InvalidFormat,
+    InvalidRoom,
+    InvalidBestOf,
+    NotFound,
+    NotOrganizer,
+    AlreadyStarted,
+    AlreadyJoined,
+    NotEnoughPlayers,
+}
+
+impl std::fmt::Display for TournamentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.localize(DEFAULT_LANGUAGE))
+    }
+}
+
+impl std::error::Error for TournamentError {}
+
+/// A best-of-N match, a match of one player is a bye.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Match {
+    pub players: Vec<PID>,
+    pub wins: Vec<usize>,
+    pub games: usize,
+    pub winner: Option<PID>,
+    #[serde(skip)]
+    game: Option<usize>,
+}
+
+impl Match {
+    fn new(players: Vec<PID>) -> Match {
+        let winner = if players.len() == 1 {
+            Some(players[0])
+        } else {
+            None
+        };
+        Match {
+            wins: vec![0; players.len()],
+            players,
+            games: 0,
+            winner,
+            game: None,
+        }
+    }
+
+    fn is_bye(&self) -> bool {
+        self.players.len() == 1
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.game.is_some()
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Standing {
+    pub pid: PID,
+    pub matches: usize,
+    pub match_wins: usize,
+    pub game_wins: usize,
+    pub game_losses: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Tournament {
+    pub name: String,
+    pub organizer: PID,
+    pub format: Format,
+    /// The room the games are played in, `None` is the default room.
+    pub room: Option<String>,
+    pub best_of: usize,
+    pub players: Vec<PID>,
+    pub stage: Stage,
+    pub rounds: Vec<Vec<Match>>,
+}
+
+/// The top seeds meet as late as possible, `size` is a power of two.
+fn bracket_order(size: usize) -> Vec<usize> {
+    let mut order = vec![0];
+    while order.len() < size {
+        let len = order.len() * 2;
+        order = order
+            .into_iter()
+            .flat_map(|seed| vec![seed, len - 1 - seed])
+            .collect();
+    }
+    order
+}
+
+/// The circle method, a player without an opponent has a bye.
+fn round_robin(players: &[PID]) -> Vec<Vec<Match>> {
+    let mut circle = players.iter().map(|&pid| Some(pid)).collect::<Vec<_>>();
+    if circle.len() % 2 == 1 {
+        circle.push(None);
+    }
+    let n = circle.len();
+
+    (0..n - 1)
+        .map(|_| {
+            let round = (0..n / 2)
+                .filter_map(|i| match (circle[i], circle[n - 1 - i]) {
+                    (Some(a), Some(b)) => Some(Match::new(vec![a, b])),
+                    (Some(a), None) | (None, Some(a)) => Some(Match::new(vec![a])),
+                    (None, None) => None,
+                })
+                .collect();
+            circle[1..].rotate_right(1);
+            round
+        })
+        .collect()
+}
+
+impl Tournament {
+    pub fn new(
+        name: &str,
+        organizer: PID,
+        format: Format,
+        room: Option<String>,
+        best_of: usize,
+    ) -> Result<Tournament, TournamentError> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+            return Err(TournamentError::InvalidName);
+        }
+        if best_of % 2 != 1 || best_of > MAX_BEST_OF {
+            return Err(TournamentError::InvalidBestOf);
+        }
+
+        Ok(Tournament {
+            name: name.to_string(),
+            organizer,
+            format,
+            room,
+            best_of,
+            players: Vec::new(),
+            stage: Stage::Registration,
+            rounds: Vec::new(),
+        })
+    }
+
+    pub fn join(&mut self, pid: PID) -> Result<(), TournamentError> {
+        if self.stage != Stage::Registration {
+            Err(TournamentError::AlreadyStarted)
+        } else if self.players.contains(&pid) {
+            Err(TournamentError::AlreadyJoined)
+        } else {
+            self.players.push(pid);
+            Ok(())
+        }
+    }
+
+    /// Makes the first round, or all of them for a round-robin.
+    pub fn start<F: Fn(PID) -> f64>(&mut self, pid: PID, rating: F) -> Result<(), TournamentError> {
+        if pid != self.organizer {
+            return Err(TournamentError::NotOrganizer);
+        }
+        if self.stage != Stage::Registration {
+            return Err(TournamentError::AlreadyStarted);
+        }
+        if self.players.len() < 2 {
+            return Err(TournamentError::NotEnoughPlayers);
+        }
+
+        let mut seeds = self.players.clone();
+        seeds.sort_by(|&a, &b| rating(b).partial_cmp(&rating(a)).unwrap());
+
+        self.rounds = match self.format {
+            Format::RoundRobin => round_robin(&seeds),
+            Format::SingleElimination => {
+                let size = seeds.len().next_power_of_two();
+                let order = bracket_order(size);
+                let round = order
+                    .chunks(2)
+                    .map(|pair| {
+                        Match::new(
+                            pair.iter()
+                                .filter_map(|&seed| seeds.get(seed).copied())
+                                .collect(),
+                        )
+                    })
+                    .collect();
+                vec![round]
+            }
+        };
+        self.stage = Stage::Running;
+        self.advance();
+        Ok(())
+    }
+
+    /// The earliest unfinished match of the player.
+    fn current_match(&mut self, pid: PID) -> Option<&mut Match> {
+        if self.stage != Stage::Running {
+            return None;
+        }
+        self.rounds
+            .iter_mut()
+            .flatten()
+            .find(|m| m.winner.is_none() && m.players.contains(&pid))
+    }
+
+    pub fn opponent(&self, pid: PID) -> Option<PID> {
+        if self.stage != Stage::Running {
+            return None;
+        }
+        let m = self
+            .rounds
+            .iter()
+            .flatten()
+            .find(|m| m.winner.is_none() && m.players.contains(&pid))?;
+        m.players.iter().copied().find(|&id| id != pid)
+    }
+
+    pub fn has_match(&self, pid: PID) -> bool {
+        self.opponent(pid).is_some()
+    }
+
+    pub fn game_started(&mut self, players: &[PID], game: usize) -> bool {
+        match self.current_match(players[0]) {
+            Some(m) if players.iter().all(|pid| m.players.contains(pid)) => {
+                m.game = Some(game);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns `false` if it isn't a game of the tournament.
+    fn game_finished(&mut self, game: usize, winner: Option<PID>) -> bool {
+        let best_of = self.best_of;
+        let m = match self
+            .rounds
+            .iter_mut()
+            .flatten()
+            .find(|m| m.game == Some(game))
+        {
+            Some(m) => m,
+            None => return false,
+        };
+
+        m.game = None;
+        if let Some(pos) = winner.and_then(|pid| m.players.iter().position(|&id| id == pid)) {
+            m.games += 1;
+            m.wins[pos] += 1;
+            if m.wins[pos] > best_of / 2 {
+                m.winner = winner;
+            }
+        }
+        self.advance();
+        true
+    }
+
+    /// Makes the next single-elimination round once the current one is over.
+    fn advance(&mut self) {
+        loop {
+            let last = match self.rounds.last() {
+                Some(round) => round,
+                None => return,
+            };
+            if last.iter().any(|m| m.winner.is_none()) {
+                return;
+            }
+            if self.format == Format::RoundRobin || last.len() == 1 {
+                self.stage = Stage::Finished;
+                return;
+            }
+
+            let next = last
+                .chunks(2)
+                .map(|pair| Match::new(pair.iter().filter_map(|m| m.winner).collect()))
+                .collect();
+            self.rounds.push(next);
+        }
+    }
+
+    /// The last match winner or the leader of the standings.
+    pub fn winner(&self) -> Option<PID> {
+        if self.stage != Stage::Finished {
+            return None;
+        }
+        match self.format {
+            Format::SingleElimination => self.rounds.last()?.first()?.winner,
+            Format::RoundRobin => self.standings().first().map(|standing| standing.pid),
+        }
+    }
+
+    /// By won matches, then by the difference of won and lost games.
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings = self
+            .players
+            .iter()
+            .map(|&pid| {
+                (
+                    pid,
+                    Standing {
+                        pid,
+                        ..Standing::default()
+                    },
+                )
+            })
+            .collect::<HashMap<_, _>>();
+
+        for m in self.rounds.iter().flatten().filter(|m| !m.is_bye()) {
+            let total = m.wins.iter().sum::<usize>();
+            for (&pid, &wins) in m.players.iter().zip(m.wins.iter()) {
+                if let Some(standing) = standings.get_mut(&pid) {
+                    if m.winner.is_some() {
+                        standing.matches += 1;
+                    }
+                    if m.winner == Some(pid) {
+                        standing.match_wins += 1;
+                    }
+                    standing.game_wins += wins;
+                    standing.game_losses += total - wins;
+                }
+            }
+        }
+
+        let mut standings = standings.into_values().collect::<Vec<_>>();
+        standings.sort_by_key(|s| {
+            (
+                std::cmp::Reverse(s.match_wins),
+                std::cmp::Reverse(s.game_wins as i64 - s.game_losses as i64),
+                s.pid,
+            )
+        });
+        standings
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Tournaments {
+    tournaments: Vec<Tournament>,
+}
+
+impl Tournaments {
+    pub fn load() -> Tournaments {
+        let tournaments = match File::open(TOURNAMENTS_FILE) {
+            Ok(file) => match serde_json::from_reader::<_, Tournaments>(file) {
+                Ok(tournaments) => tournaments,
+                Err(e) => {
+                    warn!("TOURNAMENTS can't parse {}: {}", TOURNAMENTS_FILE, e);
+                    Tournaments::default()
+                }
+            },
+            Err(_) => Tournaments::default(),
+        };
+        info!("TOURNAMENTS {} loaded", tournaments.tournaments.len());
+        tournaments
+    }
+
+    fn save(&self) {
+        match File::create(TOURNAMENTS_FILE) {
+            Ok(file) => {
+                if let Err(e) = serde_json::to_writer(file, self) {
+                    warn!("TOURNAMENTS can't save {}: {}", TOURNAMENTS_FILE, e);
+                }
+            }
+            Err(e) => warn!("TOURNAMENTS can't create {}: {}", TOURNAMENTS_FILE, e),
+        }
+    }
+
+    pub fn create(&mut self, tournament: Tournament) -> usize {
+        self.tournaments.push(tournament);
+        self.save();
+        self.tournaments.len()
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Tournament> {
+        self.tournaments.get(id.checked_sub(1)?)
+    }
+
+    /// Saves the tournaments if the change succeeded.
+    pub fn update<F>(&mut self, id: usize, f: F) -> Result<(), TournamentError>
+    where
+        F: FnOnce(&mut Tournament) -> Result<(), TournamentError>,
+    {
+        let tournament = id
+            .checked_sub(1)
+            .and_then(|index| self.tournaments.get_mut(index))
+            .ok_or(TournamentError::NotFound)?;
+        f(tournament)?;
+        self.save();
+        Ok(())
+    }
+
+    /// The latest first.
+    pub fn list(&self) -> Vec<(usize, &Tournament)> {
+        self.tournaments
+            .iter()
+            .enumerate()
+            .map(|(index, tournament)| (index + 1, tournament))
+            .rev()
+            .collect()
+    }
+
+    pub fn of_player(&self, pid: PID) -> Option<(usize, &Tournament)> {
+        self.list()
+            .into_iter()
+            .find(|(_, tournament)| tournament.has_match(pid))
+    }
+
+    pub fn game_started(&mut self, id: usize, players: &[PID], game: usize) -> bool {
+        match id
+            .checked_sub(1)
+            .and_then(|index| self.tournaments.get_mut(index))
+        {
+            Some(tournament) => tournament.game_started(players, game),
+            None => false,
+        }
+    }
+
+    pub fn game_finished(&mut self, game: usize, winner: Option<PID>) -> Option<usize> {
+        let index = self
+            .tournaments
+            .iter_mut()
+            .position(|tournament| tournament.game_finished(game, winner))?;
+        self.save();
+        Some(index + 1)
+    }
+}
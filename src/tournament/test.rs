@@ -0,0 +1,127 @@
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashSet;
+
+    use crate::tournament::*;
+
+    fn play(tournament: &mut Tournament, game: usize, winner: PID) {
+        let loser = tournament.opponent(winner).unwrap();
+        assert!(tournament.game_started(&[winner, loser], game));
+        assert!(tournament.game_finished(game, Some(winner)));
+    }
+
+    #[test]
+    fn tournament_test_bracket_order() {
+        assert_eq!(bracket_order(1), vec![0]);
+        assert_eq!(bracket_order(4), vec![0, 3, 1, 2]);
+        assert_eq!(bracket_order(8), vec![0, 7, 3, 4, 1, 6, 2, 5]);
+    }
+
+    #[test]
+    fn tournament_test_round_robin() {
+        let rounds = round_robin(&[1, 2, 3, 4, 5]);
+        assert_eq!(rounds.len(), 5);
+
+        let mut pairs = HashSet::new();
+        for round in rounds.iter() {
+            assert_eq!(round.iter().filter(|m| m.is_bye()).count(), 1);
+            let mut players = round
+                .iter()
+                .flat_map(|m| m.players.clone())
+                .collect::<Vec<_>>();
+            players.sort();
+            assert_eq!(players, vec![1, 2, 3, 4, 5]);
+            for m in round.iter().filter(|m| !m.is_bye()) {
+                let (a, b) = (m.players[0], m.players[1]);
+                assert!(pairs.insert((a.min(b), a.max(b))));
+            }
+        }
+        assert_eq!(pairs.len(), 10);
+    }
+
+    #[test]
+    fn tournament_test_errors() {
+        assert_eq!(
+            Tournament::new(" ", 1, Format::RoundRobin, None, 1).unwrap_err(),
+            TournamentError::InvalidName
+        );
+        assert_eq!(
+            Tournament::new("Cup", 1, Format::RoundRobin, None, 2).unwrap_err(),
+            TournamentError::InvalidBestOf
+        );
+
+        let mut tournament = Tournament::new("Cup", 1, Format::RoundRobin, None, 1).unwrap();
+        assert_eq!(tournament.join(2), Ok(()));
+        assert_eq!(tournament.join(2), Err(TournamentError::AlreadyJoined));
+        assert_eq!(
+            tournament.start(1, |_| 0.0),
+            Err(TournamentError::NotEnoughPlayers)
+        );
+        tournament.join(3).unwrap();
+        assert_eq!(
+            tournament.start(2, |_| 0.0),
+            Err(TournamentError::NotOrganizer)
+        );
+        assert_eq!(tournament.start(1, |_| 0.0), Ok(()));
+        assert_eq!(tournament.join(4), Err(TournamentError::AlreadyStarted));
+    }
+
+    #[test]
+    fn tournament_test_single_elimination() {
+        let mut tournament = Tournament::new("Cup", 1, Format::SingleElimination, None, 3).unwrap();
+        for pid in 1..=3 {
+            tournament.join(pid).unwrap();
+        }
+        // The best rated player gets the bye
+        tournament.start(1, |pid| pid as f64).unwrap();
+        assert_eq!(tournament.rounds[0].len(), 2);
+        assert_eq!(tournament.opponent(3), None);
+        assert_eq!(tournament.opponent(1), Some(2));
+
+        play(&mut tournament, 1, 1);
+        assert!(tournament.game_started(&[1, 2], 2));
+        assert!(tournament.game_finished(2, None));
+        play(&mut tournament, 3, 2);
+        assert_eq!(tournament.rounds.len(), 1);
+        play(&mut tournament, 4, 1);
+
+        assert_eq!(tournament.rounds.len(), 2);
+        assert_eq!(tournament.opponent(3), Some(1));
+        assert!(!tournament.game_finished(4, Some(1)));
+
+        play(&mut tournament, 5, 3);
+        play(&mut tournament, 6, 3);
+        assert_eq!(tournament.stage, Stage::Finished);
+        assert_eq!(tournament.winner(), Some(3));
+        assert!(!tournament.has_match(1));
+
+        let standings = tournament.standings();
+        assert_eq!(standings[0].pid, 3);
+        assert_eq!(standings[1].pid, 1);
+        assert_eq!(standings[1].game_wins, 2);
+        assert_eq!(standings[1].game_losses, 3);
+    }
+
+    #[test]
+    fn tournament_test_pool() {
+        let mut tournaments = Tournaments::default();
+        let mut tournament = Tournament::new("Cup", 1, Format::RoundRobin, None, 1).unwrap();
+        tournament.join(1).unwrap();
+        tournament.join(2).unwrap();
+        tournaments.tournaments.push(tournament);
+
+        assert_eq!(
+            tournaments.update(2, |_| Ok(())),
+            Err(TournamentError::NotFound)
+        );
+        assert!(tournaments.of_player(1).is_none());
+        tournaments.tournaments[0].start(1, |_| 0.0).unwrap();
+        assert_eq!(tournaments.of_player(2).unwrap().0, 1);
+
+        assert!(tournaments.tournaments[0].game_started(&[2, 1], 7));
+        assert_eq!(tournaments.game_finished(8, Some(2)), None);
+        assert!(tournaments.tournaments[0].game_finished(7, Some(2)));
+        assert_eq!(tournaments.get(1).unwrap().winner(), Some(2));
+    }
+}
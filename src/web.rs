@@ -0,0 +1,344 @@
+//! The handler runs on the blocking thread pool, it locks the shared state and
+//! hashes passwords.
+
+use std::borrow::Cow;
+use std::convert::Infallible;
+use std::future::Future;
+use std::io::Write;
+use std::net::SocketAddr;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use percent_encoding::percent_decode_str;
+use serde::Serialize;
+use warp::http::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_LENGTH};
+use warp::http::{Method, StatusCode};
+use warp::hyper::body::Bytes;
+use warp::path::FullPath;
+use warp::ws::{WebSocket, Ws};
+use warp::{Filter, Rejection, Reply};
+
+mod test;
+
+/// Larger requests are refused.
+const MAX_BODY_SIZE: u64 = 64 * 1024;
+
+/// Returns the 400 response if the expression is an error.
+#[macro_export]
+macro_rules! try_or_400 {
+    ($result:expr) => {
+        match $result {
+            Ok(value) => value,
+            Err(_) => return $crate::web::Response::empty_400(),
+        }
+    };
+}
+
+/// Returns the 404 response if the expression is an error.
+#[macro_export]
+macro_rules! try_or_404 {
+    ($result:expr) => {
+        match $result {
+            Ok(value) => value,
+            Err(_) => return $crate::web::Response::empty_404(),
+        }
+    };
+}
+
+pub struct Request {
+    method: Method,
+    path: String,
+    query: String,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl Request {
+    pub fn new(
+        method: Method,
+        path: &str,
+        query: &str,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> Request {
+        Request {
+            method,
+            path: path.to_string(),
+            query: query.to_string(),
+            headers,
+            body,
+        }
+    }
+
+    pub fn method(&self) -> &str {
+        self.method.as_str()
+    }
+
+    pub fn url(&self) -> String {
+        percent_decode_str(&self.path)
+            .decode_utf8_lossy()
+            .into_owned()
+    }
+
+    /// `/` has no segments.
+    pub fn segments(&self) -> Vec<String> {
+        self.path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| percent_decode_str(segment).decode_utf8_lossy().into_owned())
+            .collect()
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name)?.to_str().ok()
+    }
+
+    /// The path of the referring page if it is on the host.
+    pub fn referer_path(&self, host: &str) -> Option<&str> {
+        let referer = self.header("Referer")?;
+        let url = referer
+            .strip_prefix("https://")
+            .or_else(|| referer.strip_prefix("http://"))?;
+        let path = url.strip_prefix(host)?;
+        let path = path.split('#').next().unwrap_or_default();
+        // Browsers take `//` and `/\` for the start of another host
+        match path.as_bytes() {
+            [] => Some("/"),
+            [b'/', b'/', ..] | [b'/', b'\\', ..] => None,
+            [b'/', ..] => Some(path),
+            _ => None,
+        }
+    }
+
+    /// A parameter without a value is an empty string.
+    pub fn get_param(&self, name: &str) -> Option<String> {
+        serde_urlencoded::from_str::<Vec<(String, String)>>(&self.query)
+            .ok()?
+            .into_iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value)
+    }
+
+    pub fn cookie(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get_all("Cookie")
+            .iter()
+            .filter_map(|header| header.to_str().ok())
+            .flat_map(|header| header.split(';'))
+            .filter_map(|cookie| cookie.split_once('='))
+            .find(|(n, _)| n.trim() == name)
+            .map(|(_, value)| value.trim())
+    }
+
+    pub fn form(&self) -> Result<Vec<(String, String)>, serde_urlencoded::de::Error> {
+        serde_urlencoded::from_bytes(&self.body)
+    }
+}
+
+pub struct Response {
+    pub status_code: u16,
+    pub headers: Vec<(Cow<'static, str>, Cow<'static, str>)>,
+    pub data: Vec<u8>,
+}
+
+impl Response {
+    pub fn from_data<C, D>(content_type: C, data: D) -> Response
+    where
+        C: Into<Cow<'static, str>>,
+        D: Into<Vec<u8>>,
+    {
+        Response {
+            status_code: 200,
+            headers: vec![("Content-Type".into(), content_type.into())],
+            data: data.into(),
+        }
+    }
+
+    pub fn json<T: Serialize>(content: &T) -> Response {
+        Response::from_data("application/json", serde_json::to_vec(content).unwrap())
+    }
+
+    pub fn redirect_303<S: Into<Cow<'static, str>>>(target: S) -> Response {
+        Response::empty(303).with_unique_header("Location", target)
+    }
+
+    pub fn empty(status_code: u16) -> Response {
+        Response {
+            status_code,
+            headers: vec![],
+            data: vec![],
+        }
+    }
+
+    pub fn empty_400() -> Response {
+        Response::empty(400)
+    }
+
+    pub fn empty_404() -> Response {
+        Response::empty(404)
+    }
+
+    pub fn with_status_code(mut self, status_code: u16) -> Response {
+        self.status_code = status_code;
+        self
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_ref())
+    }
+
+    pub fn with_additional_header<H, C>(mut self, name: H, value: C) -> Response
+    where
+        H: Into<Cow<'static, str>>,
+        C: Into<Cow<'static, str>>,
+    {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Replaces the headers with the same name.
+    pub fn with_unique_header<H, C>(mut self, name: H, value: C) -> Response
+    where
+        H: Into<Cow<'static, str>>,
+        C: Into<Cow<'static, str>>,
+    {
+        let name = name.into();
+        self.headers.retain(|(n, _)| !n.eq_ignore_ascii_case(&name));
+        self.with_additional_header(name, value)
+    }
+
+    /// Lets browsers and proxies use the response for the given time.
+    pub fn with_public_cache(self, max_age: u64) -> Response {
+        self.with_unique_header("Cache-Control", format!("public, max-age={}", max_age))
+    }
+}
+
+impl Reply for Response {
+    fn into_response(self) -> warp::reply::Response {
+        let mut response = warp::reply::Response::new(self.data.into());
+        *response.status_mut() =
+            StatusCode::from_u16(self.status_code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        for (name, value) in self.headers {
+            match (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(&value),
+            ) {
+                (Ok(name), Ok(value)) => {
+                    response.headers_mut().append(name, value);
+                }
+                _ => warn!("HTTP invalid header {}: {}", name, value),
+            }
+        }
+        response
+    }
+}
+
+/// Compresses text responses with gzip for browsers accepting it.
+pub fn compress(request: &Request, response: Response) -> Response {
+    let accepted = request.header("Accept-Encoding").is_some_and(|header| {
+        header
+            .split(',')
+            .any(|encoding| encoding.split(';').next().unwrap().trim() == "gzip")
+    });
+    let text = response.header("Content-Type").is_some_and(|content_type| {
+        content_type.starts_with("text/")
+            || content_type.contains("json")
+            || content_type.contains("javascript")
+            || content_type.contains("xml")
+    });
+    if !accepted
+        || !text
+        || response.data.is_empty()
+        || response.header("Content-Encoding").is_some()
+    {
+        return response;
+    }
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    match encoder
+        .write_all(&response.data)
+        .and_then(|()| encoder.finish())
+    {
+        Ok(data) => Response { data, ..response }
+            .with_unique_header("Content-Encoding", "gzip")
+            .with_additional_header("Vary", "Accept-Encoding"),
+        Err(_) => response,
+    }
+}
+
+/// The handler runs in a task of its own.
+pub fn websocket<F, U>(ws: Ws, protocol: Option<&'static str>, handler: F) -> Response
+where
+    F: FnOnce(WebSocket) -> U + Send + 'static,
+    U: Future<Output = ()> + Send + 'static,
+{
+    let reply = ws.on_upgrade(handler).into_response();
+    let mut response = Response::empty(reply.status().as_u16());
+    for (name, value) in reply.headers() {
+        if let Ok(value) = value.to_str() {
+            response = response.with_additional_header(name.to_string(), value.to_string());
+        }
+    }
+    match protocol {
+        Some(protocol) => response.with_unique_header("Sec-WebSocket-Protocol", protocol),
+        None => response,
+    }
+}
+
+/// The request with the websocket it asks for, `None` if its body is too large.
+fn request() -> impl Filter<Extract = (Option<Request>, Option<Ws>), Error = Infallible> + Clone {
+    let ws = warp::ws().map(Some).or(warp::any().map(|| None)).unify();
+    let query = warp::query::raw().or(warp::any().map(String::new)).unify();
+    // Bodies of requests without a length aren't read
+    let body = warp::body::content_length_limit(MAX_BODY_SIZE)
+        .and(warp::body::bytes())
+        .map(Some)
+        .or(warp::header::headers_cloned().map(|headers: HeaderMap| {
+            let too_large = headers
+                .get(CONTENT_LENGTH)
+                .and_then(|length| length.to_str().ok()?.parse::<u64>().ok())
+                .is_some_and(|length| length > MAX_BODY_SIZE);
+            (!too_large).then(Bytes::new)
+        }))
+        .unify();
+    ws.and(warp::method())
+        .and(warp::path::full())
+        .and(query)
+        .and(warp::header::headers_cloned())
+        .and(body)
+        .map(|ws, method, path: FullPath, query: String, headers, body| {
+            let request = Option::map(body, |body: Bytes| {
+                Request::new(method, path.as_str(), &query, headers, body)
+            });
+            (request, ws)
+        })
+        .untuple_one()
+}
+
+/// Waits for the requests in progress after `shutdown` completes, websockets
+/// are left to their tasks.
+pub async fn serve<S, F>(addr: SocketAddr, shutdown: S, handler: F) -> Result<(), warp::Error>
+where
+    S: Future<Output = ()> + Send + 'static,
+    F: Fn(Request, Option<Ws>) -> Response + Clone + Send + Sync + 'static,
+{
+    let routes = request().and_then(move |request: Option<Request>, ws| {
+        let handler = handler.clone();
+        async move {
+            let request = match request {
+                Some(request) => request,
+                None => return Ok::<_, Rejection>(Response::empty(413)),
+            };
+            let response = tokio::task::spawn_blocking(move || handler(request, ws))
+                .await
+                .unwrap_or_else(|_| Response::empty(500));
+            Ok(response)
+        }
+    });
+    let (_, server) = warp::serve(routes).try_bind_with_graceful_shutdown(addr, shutdown)?;
+    server.await;
+    Ok(())
+}
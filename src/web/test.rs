@@ -0,0 +1,127 @@
+#[cfg(test)]
+mod tests {
+
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+    use warp::http::header::{HeaderMap, HeaderValue};
+    use warp::http::Method;
+    use warp::hyper::body::Bytes;
+
+    use crate::web::*;
+
+    fn request(path: &str, query: &str, headers: &[(&'static str, &str)]) -> Request {
+        let mut map = HeaderMap::new();
+        for &(name, value) in headers {
+            map.append(name, HeaderValue::from_str(value).unwrap());
+        }
+        Request::new(Method::GET, path, query, map, Bytes::new())
+    }
+
+    #[test]
+    fn web_test_request() {
+        let request = request(
+            "/player/%D0%98%D0%B2%D0%B0%D0%BD/",
+            "room=blitz&arena&token=a%2Bb",
+            &[("Cookie", "lang=ru; sid=abc "), ("Accept-Language", "en")],
+        );
+        assert_eq!(request.method(), "GET");
+        assert_eq!(request.url(), "/player/Иван/");
+        assert_eq!(request.segments(), vec!["player", "Иван"]);
+        assert_eq!(request.get_param("room").as_deref(), Some("blitz"));
+        assert_eq!(request.get_param("arena").as_deref(), Some(""));
+        assert_eq!(request.get_param("token").as_deref(), Some("a+b"));
+        assert_eq!(request.get_param("rematch"), None);
+        assert_eq!(request.cookie("sid"), Some("abc"));
+        assert_eq!(request.cookie("lang"), Some("ru"));
+        assert_eq!(request.cookie("id"), None);
+        assert_eq!(request.header("accept-language"), Some("en"));
+        assert!(self::request("/", "", &[]).segments().is_empty());
+
+        let form = Request::new(
+            Method::POST,
+            "/login",
+            "",
+            HeaderMap::new(),
+            Bytes::from("nickname=%D0%AF&password=a+b%26c"),
+        );
+        assert_eq!(
+            form.form().unwrap(),
+            vec![
+                ("nickname".to_string(), "Я".to_string()),
+                ("password".to_string(), "a b&c".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn web_test_referer_path() {
+        let referer = |url| request("/lang/en", "", &[("Referer", url)]);
+        let host = "durak.example";
+        let path = |url| referer(url).referer_path(host).map(str::to_string);
+
+        assert_eq!(
+            path("https://durak.example/game?room=blitz#chat").as_deref(),
+            Some("/game?room=blitz")
+        );
+        assert_eq!(path("http://durak.example").as_deref(), Some("/"));
+        assert_eq!(path("https://evil.example/?x=durak.example"), None);
+        assert_eq!(path("https://durak.example.evil.example/"), None);
+        assert_eq!(path("https://durak.example@evil.example/"), None);
+        assert_eq!(path("https://durak.example//evil.example/"), None);
+        assert_eq!(path("https://durak.example/\\evil.example/"), None);
+        assert_eq!(path("javascript://durak.example/"), None);
+        assert_eq!(request("/", "", &[]).referer_path(host), None);
+    }
+
+    #[tokio::test]
+    async fn web_test_request_body() {
+        let form = |request: warp::test::RequestBuilder| async {
+            let (request, _) = request.filter(&crate::web::request()).await.unwrap();
+            request.map(|request| request.form().unwrap())
+        };
+
+        let body = "nickname=a";
+        let pair = vec![("nickname".to_string(), "a".to_string())];
+        assert_eq!(
+            form(warp::test::request().method("POST").body(body)).await,
+            Some(pair)
+        );
+        assert_eq!(
+            form(warp::test::request().method("POST")).await,
+            Some(vec![])
+        );
+        let large = "a".repeat(MAX_BODY_SIZE as usize + 1);
+        assert_eq!(
+            form(warp::test::request().method("POST").body(large)).await,
+            None
+        );
+    }
+
+    #[test]
+    fn web_test_response() {
+        let response = Response::redirect_303("/game")
+            .with_additional_header("Set-Cookie", "a=1")
+            .with_additional_header("Set-Cookie", "b=2")
+            .with_unique_header("location", "/login");
+        assert_eq!(response.status_code, 303);
+        assert_eq!(response.header("Location"), Some("/login"));
+        assert_eq!(response.headers.len(), 3);
+
+        let text = "a page ".repeat(100);
+        let gzip = request("/", "", &[("Accept-Encoding", "deflate, gzip;q=1.0")]);
+        let compressed = compress(&gzip, Response::from_data("text/html", text.clone()));
+        assert_eq!(compressed.header("Content-Encoding"), Some("gzip"));
+        let mut data = String::new();
+        GzDecoder::new(&compressed.data[..])
+            .read_to_string(&mut data)
+            .unwrap();
+        assert_eq!(data, text);
+
+        let plain = request("/", "", &[("Accept-Encoding", "br")]);
+        let response = compress(&plain, Response::from_data("text/html", text.clone()));
+        assert_eq!(response.data, text.as_bytes());
+        let response = compress(&gzip, Response::from_data("image/png", text.clone()));
+        assert_eq!(response.header("Content-Encoding"), None);
+    }
+}
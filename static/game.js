@@ -1,16 +1,34 @@
-var socket = new WebSocket('ws://{host}/ws', 'echo');
+const PROTOCOL_VERSION = 2;
+
+var socket = new WebSocket('ws://' + CONFIG.host + '/ws' +
+    (CONFIG.rematch !== null ? '?rematch=' + CONFIG.rematch :
+        CONFIG.tournament !== null ? '?tournament=' + CONFIG.tournament :
+        CONFIG.room !== null ? '?room=' + encodeURIComponent(CONFIG.room) : ''), 'echo');
 var cards = new Set();
 var is_your_turn = false;
 var deck_size = 0;
 var net_time = 0;
 var timeout = 0;
+var clock = null;
 
 const SUIT = 1;
 const RANK = 0;
 
-function send(data) {{
-    socket.send(data);
-}}
+function send(data) {
+    socket.send(JSON.stringify(data));
+}
+
+function make_step(kind, cards) {
+    step = {'kind': kind};
+    if (cards !== undefined) {
+        step['cards'] = cards;
+    }
+    send({'type': 'MakeStep', 'step': step});
+}
+
+socket.onopen = function() {
+    send({'type': 'Hello', 'version': PROTOCOL_VERSION});
+}
 
 function parse_cards() {
     cards_arr = []
@@ -47,16 +65,15 @@ function without(a, b) {
     );
 }
 
-function refresh_state(data) {
-    if (data == JSON.parse('"Passive"')) {
+function refresh_state(board) {
+    if (board === null) {
         set_state(false);
         document.getElementById('comb').innerText = '';
         document.getElementById('board').innerText = '';
     } else {
-        data = data['Active']
         set_state(true);
-        document.getElementById('comb').innerHTML = print_cards(data['comb']['cards']);
-        document.getElementById('board').innerHTML = print_cards(without(data['cards'], data['comb']['cards']));
+        document.getElementById('comb').innerHTML = print_cards(board['comb']);
+        document.getElementById('board').innerHTML = print_cards(without(board['cards'], board['comb']));
     }
 }
 
@@ -115,71 +132,121 @@ function print_cards(cards) {
     return s;
 }
 
-socket.onmessage = function(event) {{
+socket.onmessage = function(event) {
     net_time = 0;
     refresh_netstat();
 
-    data = event.data;
-    if (data != '"Pong"') {
-        data = JSON.parse(data);
-        if (data['YourCards']) {
+    data = JSON.parse(event.data);
+    switch (data['type']) {
+        case 'Welcome':
+            document.getElementById('Nickname').innerText = data['nickname'];
+            break;
+        case 'ProtocolError':
+            alert(T.game.protocol_error);
+            break;
+        case 'Opponents':
+            document.getElementById('opponents').innerText = data['opponents']
+                .map(opponent => opponent['nickname'] + ' (' + opponent['rating'] + ')')
+                .join(', ');
+            break;
+        case 'YourCards':
             document.getElementById('WaitDiv').style.display = 'None'
             document.getElementById('GameDiv').style.display = '';
-            document.getElementById('cards').innerHTML = print_cards(data['YourCards'][0]);
-            document.getElementById('deck_size').innerText = data['YourCards'][1];
-            deck_size = data['YourCards'][1] + 0;
-        } else if (data['YourTurn']) {
-            data = data['YourTurn'];
-            document.getElementById('your_turn').innerText = 'Да';
-            timeout = data[4];
+            document.getElementById('cards').innerHTML = print_cards(data['cards']);
+            document.getElementById('deck_size').innerText = data['deck_size'];
+            deck_size = data['deck_size'];
+            break;
+        case 'GameSnapshot':
+            document.getElementById('WaitDiv').style.display = 'None'
+            document.getElementById('GameDiv').style.display = '';
+            is_your_turn = data['your_turn'];
+            document.getElementById('your_turn').innerText = is_your_turn ? T.common.yes : T.common.no;
+            timeout = data['time_left'];
+            clock = data['clock'];
+            document.getElementById('cards').innerHTML = print_cards(data['cards']);
+            cards.clear();
+            document.getElementById('deck_size').innerText = data['deck_size'];
+            document.getElementById('opponent_deck').innerText = data['opponents']
+                .map(opponent => opponent['playing'] ? opponent['cards'] : '-')
+                .join(', ');
+            deck_size = data['deck_size'];
+            refresh_state(data['board']);
+            refresh_timeout();
+            break;
+        case 'YourTurn':
+            document.getElementById('your_turn').innerText = T.common.yes;
+            timeout = data['time_left'];
             is_your_turn = true;
-            document.getElementById('cards').innerHTML = print_cards(data[1]);
-            document.getElementById('deck_size').innerText = data[2];
-            document.getElementById('opponent_deck').innerText = data[3];
-            deck_size = data[2] + 0;
-            refresh_state(data[0]);    
+            document.getElementById('cards').innerHTML = print_cards(data['cards']);
+            document.getElementById('deck_size').innerText = data['deck_size'];
+            document.getElementById('opponent_deck').innerText = data['opponent_cards'];
+            deck_size = data['deck_size'];
+            refresh_state(data['board']);
             cards_clear();
-        } else if (data['YouMadeStep']) {
-            data = data['YouMadeStep'];
-            document.getElementById('your_turn').innerText = 'Нет';
+            break;
+        case 'YouMadeStep':
+            document.getElementById('your_turn').innerText = T.common.no;
             is_your_turn = false;
-            document.getElementById('cards').innerHTML = print_cards(data[1]);
-            document.getElementById('deck_size').innerText = data[2];
-            document.getElementById('opponent_deck').innerText = data[3];
-            deck_size = data[2] + 0;
-            refresh_state(data[0]);
-        } else if (data == 'GameWinner') {
+            document.getElementById('cards').innerHTML = print_cards(data['cards']);
+            document.getElementById('deck_size').innerText = data['deck_size'];
+            document.getElementById('opponent_deck').innerText = data['opponent_cards'];
+            deck_size = data['deck_size'];
+            refresh_state(data['board']);
+            break;
+        case 'GameWinner':
             location.replace('/winner');
-        } else if (data == 'GameLoser') {
+            break;
+        case 'GameLoser':
             location.replace('/loser');
-        } else if (data['ID']) {
-            document.getElementById('GamePID').innerText = data['ID'];
-        } else if (data['StepError'] == 'WeakComb') {
-            alert('Слишком слабая комбинация');
-        } else if (data['StepError'] == 'InvalidComb') {
-            alert('Неверная комбинация');
-        } else if (data['Message']) {
-            document.getElementById('chatbox').value += "   > " + data['Message'] + '\n';
-        } else if (data['Sent']) {
-            if (data['Sent']['Err'] === null) {
-                alert('Слишком длинное сообщение, не удалось отправить');
+            break;
+        case 'StepError':
+            message = data['message'];
+            context = data['context'];
+            if (context !== null && context['kind'] == 'CombToBeat') {
+                message += ': ' + context['board']['comb'].map(card => card[RANK] + card[SUIT]).join(' ');
+            } else if (context !== null && context['kind'] == 'MissingCards') {
+                message += ': ' + context['cards'].map(card => card[RANK] + card[SUIT]).join(' ');
+            }
+            alert(message);
+            break;
+        case 'Message':
+            document.getElementById('chatbox').value += data['from'] + " > " + data['text'] + '\n';
+            break;
+        case 'PlayerLeft':
+            document.getElementById('chatbox').value += data['nickname'] + ' ' + T.game.player_left + '\n';
+            break;
+        case 'OutOfTime':
+            document.getElementById('chatbox').value += data['nickname'] + ' ' + T.game.out_of_time
+                + ' (' + data['timeouts'] + ')\n';
+            break;
+        case 'Sent':
+            if (!data['ok']) {
+                alert(T.game.message_too_long);
             }
-        } else if (data == 'YouArePlaying') {
-            alert('Вы уже играете в другой вкладке')
-        }
+            break;
+        case 'YouArePlaying':
+            alert(T.game.already_playing)
+            break;
+        case 'ServerShutdown':
+            if (document.getElementById('GameDiv').style.display == '') {
+                document.getElementById('chatbox').value += T.game.server_shutdown + ' ' + data['wait'] + '\n';
+            } else {
+                alert(T.game.no_new_games);
+            }
+            break;
     }
-}}
+}
 
 function set_state(state) {
     if (state) {
-        document.getElementById('state').innerText = 'Активное';
+        document.getElementById('state').innerText = T.game.active;
         document.getElementById('GetCardBut').style.visibility = 'hidden';
         document.getElementById('GiveCombBut').style.visibility = 'hidden';
         document.getElementById('TransCombBut').style.visibility = 'visible';
         document.getElementById('GetCombBut').style.visibility = 'visible'; 
         document.getElementById('StateActDiv').style.display = '';    
     } else {
-        document.getElementById('state').innerText = 'Пассивное';
+        document.getElementById('state').innerText = T.game.passive;
         document.getElementById('GetCardBut').style.visibility = 'visible';
         document.getElementById('GiveCombBut').style.visibility = 'visible';
         document.getElementById('TransCombBut').style.visibility = 'hidden';
@@ -211,16 +278,16 @@ function add_card(card) {
 }
 
 function refresh_netstat() {
-    if (net_time >= {HEARTBIT_INTERVAL}) {
+    if (net_time >= CONFIG.heartbit_interval) {
         document.getElementById('NetStat').style.color = 'Red';
-        document.getElementById('NetStat').innerHTML = 'Обрыв соединения <a href="/">На главную страницу</a>';
+        document.getElementById('NetStat').innerHTML = T.game.connection_lost + ' <a href="/">' + T.game.home + '</a>';
         socket.close();
-    } else if (net_time >= ({HEARTBIT_INTERVAL} / 2)) {
+    } else if (net_time >= (CONFIG.heartbit_interval / 2)) {
         document.getElementById('NetStat').style.color = 'Orange';
-        document.getElementById('NetStat').innerText = 'Проблемы со связью';
+        document.getElementById('NetStat').innerText = T.game.connection_problems;
     } else {
         document.getElementById('NetStat').style.color = 'Green';
-        document.getElementById('NetStat').innerText = 'Соединение установлено';
+        document.getElementById('NetStat').innerText = T.game.connected;
     }
 }
 
@@ -231,17 +298,26 @@ function refresh_timeout() {
     } else {
         document.getElementById('TimeOut').style.display = 'none';
     }
+    if (clock !== null) {
+        document.getElementById('ClockDiv').style.display = '';
+        document.getElementById('clock').innerText = clock;
+    }
 }
 
 function chat_send() {
-    document.getElementById('chatbox').value += "Вы > " + document.getElementById('msgbox').value + '\n'
-    send(JSON.stringify({'SendMessage': document.getElementById('msgbox').value}))
+    document.getElementById('chatbox').value += T.game.you + " > " + document.getElementById('msgbox').value + '\n'
+    send({'type': 'SendMessage', 'text': document.getElementById('msgbox').value})
     document.getElementById('msgbox').value = ''
 }
 
 heartbit = function() {
-    send('"Ping"');
+    send({'type': 'Ping'});
     refresh_netstat();
-    timeout -= 1;
+    if (timeout > 0) {
+        timeout -= 1;
+    }
+    if (is_your_turn && clock !== null && clock > 0) {
+        clock -= 1;
+    }
     refresh_timeout();
 }
\ No newline at end of file